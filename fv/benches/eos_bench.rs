@@ -1,9 +1,9 @@
 extern crate fv;
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use fv::eos::eos::EquationOfState;
+use criterion::{criterion_group, criterion_main, Criterion};
+use fv::eos::{eos::EquationOfState, ideal_gas::IdealGas};
 
 fn bench_conserved_to_primitive(b: &mut Criterion) {
-    let mut eos = EquationOfState::new(1000, 1000);
+    let mut eos = EquationOfState::new(1000, 1000, IdealGas { gamma: 1.4 });
     eos.u.data.fill(1.0);
 
     b.bench_function("conserved_to_primitive", |b| {
@@ -36,7 +36,7 @@ impl<T: Default + Copy> Block1D<T> {
         n * self.len1
     }
 
-    pub fn interior(&self) -> Iterator1D<T> {
+    pub fn interior(&self) -> Iterator1D<'_, T> {
         Iterator1D {
             data: &self.data,
             current: self.nghost,
@@ -44,7 +44,7 @@ impl<T: Default + Copy> Block1D<T> {
         }
     }
 
-    pub fn interior_mut(&mut self) -> Iterator1DMut<T> {
+    pub fn interior_mut(&mut self) -> Iterator1DMut<'_, T> {
         Iterator1DMut {
             data: &mut self.data,
             current: self.nghost,
@@ -7,6 +7,7 @@ use crate::block::iterator2d::{Iterator2D, Iterator2DMut};
 
 /// A 2D data block with nvar variables, dim2 rows, dim1 columns, and nghost
 /// ghost cells.
+#[derive(Clone)]
 pub struct Block2D<T> {
     pub nvar: usize,
     pub len2: usize,
@@ -51,7 +52,7 @@ impl<T: Default + Copy> Block2D<T> {
     /// |o|x|x|x|x|x|x|o|
     /// |o|o|o|o|o|o|o|o|
     /// This iterator loops over x (interior cells)
-    pub fn interior(&self) -> Iterator2D<T> {
+    pub fn interior(&self) -> Iterator2D<'_, T> {
         Iterator2D {
             data: &self.data,
             current1: self.nghost,
@@ -63,7 +64,7 @@ impl<T: Default + Copy> Block2D<T> {
         }
     }
 
-    pub fn interior_mut(&mut self) -> Iterator2DMut<T> {
+    pub fn interior_mut(&mut self) -> Iterator2DMut<'_, T> {
         Iterator2DMut {
             data: &mut self.data,
             len1: &self.len1,
@@ -77,7 +78,7 @@ impl<T: Default + Copy> Block2D<T> {
 
     /// Expand the interior region in the x1 direction by 1
     /// and shift by an offset. Used in reconstruction.
-    pub fn interior_x1(&self, offset: i32) -> Iterator2D<T> {
+    pub fn interior_x1(&self, offset: i32) -> Iterator2D<'_, T> {
         let start = (self.nghost as i32 + offset - 1) as usize;
         let end = (self.len1 as i32 - self.nghost as i32 + offset + 1) as usize;
 
@@ -92,7 +93,7 @@ impl<T: Default + Copy> Block2D<T> {
         }
     }
 
-    pub fn interior_x1_mut(&mut self, offset: i32) -> Iterator2DMut<T> {
+    pub fn interior_x1_mut(&mut self, offset: i32) -> Iterator2DMut<'_, T> {
         let start = (self.nghost as i32 + offset - 1) as usize;
         let end = (self.len1 as i32 - self.nghost as i32 + offset + 1) as usize;
 
@@ -114,7 +115,7 @@ impl<T: Default + Copy> Block2D<T> {
     /// |o*x*x*x*x*x*x*o|
     /// |o|o|o|o|o|o|o|o|
     /// This iterator loops over * (interior cell faces)
-    pub fn interior_f1(&self) -> Iterator2D<T> {
+    pub fn interior_f1(&self) -> Iterator2D<'_, T> {
         Iterator2D {
             data: &self.data,
             current1: self.nghost,
@@ -126,7 +127,7 @@ impl<T: Default + Copy> Block2D<T> {
         }
     }
 
-    pub fn interior_f1_mut(&mut self) -> Iterator2DMut<T> {
+    pub fn interior_f1_mut(&mut self) -> Iterator2DMut<'_, T> {
         Iterator2DMut {
             data: &mut self.data,
             len1: &self.len1,
@@ -138,7 +139,7 @@ impl<T: Default + Copy> Block2D<T> {
         }
     }
 
-    pub fn interior_f2(&self) -> Iterator2D<T> {
+    pub fn interior_f2(&self) -> Iterator2D<'_, T> {
         Iterator2D {
             data: &self.data,
             current1: self.nghost,
@@ -150,7 +151,7 @@ impl<T: Default + Copy> Block2D<T> {
         }
     }
 
-    pub fn interior_f2_mut(&mut self) -> Iterator2DMut<T> {
+    pub fn interior_f2_mut(&mut self) -> Iterator2DMut<'_, T> {
         Iterator2DMut {
             data: &mut self.data,
             len1: &self.len1,
@@ -165,7 +166,7 @@ impl<T: Default + Copy> Block2D<T> {
     /// Similar to interior_x1, but in the x2 direction
     /// Expand the interior region in the x2 direction by 1
     /// and shift by an offset. Used in reconstruction.
-    pub fn interior_x2(&self, offset: i32) -> Iterator2D<T> {
+    pub fn interior_x2(&self, offset: i32) -> Iterator2D<'_, T> {
         let start = (self.nghost as i32 + offset - 1) as usize;
         let end = (self.len2 as i32 - self.nghost as i32 + offset + 1) as usize;
 
@@ -180,7 +181,7 @@ impl<T: Default + Copy> Block2D<T> {
         }
     }
 
-    pub fn interior_x2_mut(&mut self, offset: i32) -> Iterator2DMut<T> {
+    pub fn interior_x2_mut(&mut self, offset: i32) -> Iterator2DMut<'_, T> {
         let start = (self.nghost as i32 + offset - 1) as usize;
         let end = (self.len2 as i32 - self.nghost as i32 + offset + 1) as usize;
 
@@ -195,7 +196,28 @@ impl<T: Default + Copy> Block2D<T> {
         }
     }
 
-    pub fn all(&self) -> Iterator2D<T> {
+    /// Shift the interior region by offset2 in the x2 direction and
+    /// offset1 in the x1 direction. Used in flux divergence.
+    pub fn interior_shift(
+        &self,
+        offset2: i32,
+        offset1: i32,
+    ) -> Iterator2D<'_, T> {
+        let start1 = (self.nghost as i32 + offset1) as usize;
+        let start2 = (self.nghost as i32 + offset2) as usize;
+
+        Iterator2D {
+            data: &self.data,
+            current1: start1,
+            current2: start2,
+            start1,
+            len1: self.len1,
+            end1: (self.len1 as i32 - self.nghost as i32 + offset1) as usize,
+            end2: (self.len2 as i32 - self.nghost as i32 + offset2) as usize,
+        }
+    }
+
+    pub fn all(&self) -> Iterator2D<'_, T> {
         Iterator2D {
             data: &self.data,
             current1: 0,
@@ -207,7 +229,7 @@ impl<T: Default + Copy> Block2D<T> {
         }
     }
 
-    pub fn all_mut(&mut self) -> Iterator2DMut<T> {
+    pub fn all_mut(&mut self) -> Iterator2DMut<'_, T> {
         Iterator2DMut {
             data: &mut self.data,
             len1: &self.len1,
@@ -448,4 +470,26 @@ mod tests {
         // Compare the actual and expected interior points with an offset.
         assert_eq!(interior_points_x2, expected_interior_points_x2);
     }
+
+    #[test]
+    fn test_interior_shift_iterator() {
+        let nvar = 1;
+        let dim2 = 2;
+        let dim1 = 2;
+        let nghost = 1;
+        let mut data_block = Block2D::new(nvar, dim2, dim1, nghost);
+
+        // Fill the data_block with some values.
+        for i in 0..data_block.size() {
+            data_block.data[i] = i;
+        }
+
+        let shifted_x1: Vec<_> =
+            data_block.interior_shift(0, 1).map(|x| x[0]).collect();
+        let shifted_x2: Vec<_> =
+            data_block.interior_shift(1, 0).map(|x| x[0]).collect();
+
+        assert_eq!(shifted_x1, vec![6, 7, 10, 11]);
+        assert_eq!(shifted_x2, vec![9, 10, 13, 14]);
+    }
 }
@@ -1,5 +1,5 @@
-/// ! Iterator over the interior of a Block1D.
-/// ! Iterator replaces the for loops in the Block1D methods
+//! Iterator over the interior of a Block1D.
+//! Iterator replaces the for loops in the Block1D methods
 
 pub struct Iterator1D<'a, T> {
     pub data: &'a Vec<T>,
@@ -1,5 +1,5 @@
-/// ! Iterator over the interior of a Block2D.
-/// ! Iterator replaces the for loops in the Block2D methods.
+//! Iterator over the interior of a Block2D.
+//! Iterator replaces the for loops in the Block2D methods.
 
 /// An iterator over the interior of a Block2D.
 pub struct Iterator2D<'a, T> {
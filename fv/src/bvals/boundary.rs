@@ -0,0 +1,246 @@
+//! Boundary value module
+//!
+//! Fills the ghost cells of the primitive variables at the four faces of a
+//! MeshBlock. Faces shared with another MeshBlock are marked as Block and
+//! are filled by the ghost exchange of the Mesh instead.
+
+use crate::block::block2d::Block2D;
+use crate::coord::coordinates::Coordinates;
+use crate::utils::defs::{Real, IVX, IVY};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BoundaryFace {
+    InnerX1,
+    OuterX1,
+    InnerX2,
+    OuterX2,
+}
+
+impl BoundaryFace {
+    pub const ALL: [BoundaryFace; 4] = [
+        BoundaryFace::InnerX1,
+        BoundaryFace::OuterX1,
+        BoundaryFace::InnerX2,
+        BoundaryFace::OuterX2,
+    ];
+}
+
+/// User-defined boundary function filling the ghost cells of a face
+pub type UserBoundary = fn(&mut Block2D<Real>, &Coordinates, BoundaryFace);
+
+#[derive(Clone, Copy, Debug)]
+pub enum BoundaryCondition {
+    // filled by the neighboring MeshBlock
+    Block,
+    // wraps around the MeshBlock
+    Periodic,
+    // mirror image with the normal velocity reversed
+    Reflecting,
+    // zero-gradient extrapolation
+    Outflow,
+    User(UserBoundary),
+}
+
+pub struct BoundaryValues {
+    pub bcs: [BoundaryCondition; 4],
+}
+
+impl BoundaryValues {
+    pub fn new(bc: BoundaryCondition) -> Self {
+        Self { bcs: [bc; 4] }
+    }
+
+    pub fn set(&mut self, face: BoundaryFace, bc: BoundaryCondition) {
+        self.bcs[face as usize] = bc;
+    }
+
+    pub fn get(&self, face: BoundaryFace) -> BoundaryCondition {
+        self.bcs[face as usize]
+    }
+
+    /// Fill the ghost cells of all faces
+    pub fn apply(&self, w: &mut Block2D<Real>, coord: &Coordinates) {
+        for face in BoundaryFace::ALL {
+            match self.get(face) {
+                BoundaryCondition::Block => {},
+                BoundaryCondition::User(func) => func(w, coord, face),
+                bc => apply_face(w, face, bc),
+            }
+        }
+    }
+
+    /// Fill the ghost cells of all faces of a scalar field such as the
+    /// bottom elevation. User faces are extrapolated with zero gradient.
+    pub fn apply_scalar(&self, q: &mut Block2D<Real>) {
+        for face in BoundaryFace::ALL {
+            match self.get(face) {
+                BoundaryCondition::Block => {},
+                BoundaryCondition::User(_) => {
+                    apply_face(q, face, BoundaryCondition::Outflow)
+                },
+                bc => apply_face(q, face, bc),
+            }
+        }
+    }
+}
+
+/// Fill the ghost cells of one face by copying interior cells. The source
+/// cell of ghost cell k (k = 1 is adjacent to the face) depends on the
+/// boundary condition.
+fn apply_face(
+    w: &mut Block2D<Real>,
+    face: BoundaryFace,
+    bc: BoundaryCondition,
+) {
+    let nx1 = (w.len1 - 2 * w.nghost) as i32;
+    let nx2 = (w.len2 - 2 * w.nghost) as i32;
+    let ng = w.nghost as i32;
+
+    // velocity component normal to the face
+    let (nx, ivn) = match face {
+        BoundaryFace::InnerX1 | BoundaryFace::OuterX1 => (nx1, IVX),
+        BoundaryFace::InnerX2 | BoundaryFace::OuterX2 => (nx2, IVY),
+    };
+    let ntan = if ivn == IVX { nx2 } else { nx1 };
+
+    let inner = matches!(face, BoundaryFace::InnerX1 | BoundaryFace::InnerX2);
+    let flip = matches!(bc, BoundaryCondition::Reflecting);
+
+    for k in 1..=ng {
+        // ghost and source index normal to the face
+        let (ghost, src) = match (inner, bc) {
+            (true, BoundaryCondition::Periodic) => (-k, nx - k),
+            (true, BoundaryCondition::Reflecting) => (-k, k - 1),
+            (true, _) => (-k, 0),
+            (false, BoundaryCondition::Periodic) => (nx - 1 + k, k - 1),
+            (false, BoundaryCondition::Reflecting) => (nx - 1 + k, nx - k),
+            (false, _) => (nx - 1 + k, nx - 1),
+        };
+
+        for t in 0..ntan {
+            let (jg, ig, js, is) = if ivn == IVX {
+                (t, ghost, t, src)
+            } else {
+                (ghost, t, src, t)
+            };
+
+            for n in 0..w.nvar {
+                let val = w.get(n, js, is);
+                *w.set(n, jg, ig) = if flip && n == ivn { -val } else { val };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coord::coordinates::CoordinateSystem;
+    use crate::utils::defs::{NGHOST, NHYDRO};
+
+    fn setup() -> (Block2D<Real>, Coordinates) {
+        let (dim2, dim1) = (4, 5);
+        let mut w = Block2D::new(NHYDRO, dim2, dim1, NGHOST);
+        for i in 0..w.size() {
+            w.data[i] = i as Real;
+        }
+
+        let coord = Coordinates::new(
+            CoordinateSystem::Cartesian,
+            dim2,
+            dim1,
+            (0.0, 1.0),
+            (0.0, 1.0),
+        );
+
+        (w, coord)
+    }
+
+    #[test]
+    fn test_periodic() {
+        let (mut w, coord) = setup();
+        BoundaryValues::new(BoundaryCondition::Periodic).apply(&mut w, &coord);
+
+        for n in 0..NHYDRO {
+            for k in 1..=NGHOST as i32 {
+                let get = |j, i| w.get(n, j, i);
+                approx::assert_abs_diff_eq!(get(2, -k), get(2, 5 - k));
+                approx::assert_abs_diff_eq!(get(2, 4 + k), get(2, k - 1));
+                approx::assert_abs_diff_eq!(get(-k, 1), get(4 - k, 1));
+                approx::assert_abs_diff_eq!(get(3 + k, 1), get(k - 1, 1));
+            }
+        }
+    }
+
+    #[test]
+    fn test_reflecting() {
+        let (mut w, coord) = setup();
+        BoundaryValues::new(BoundaryCondition::Reflecting)
+            .apply(&mut w, &coord);
+
+        for k in 1..=NGHOST as i32 {
+            // density is mirrored
+            approx::assert_abs_diff_eq!(w.get(0, 2, -k), w.get(0, 2, k - 1));
+            approx::assert_abs_diff_eq!(w.get(0, 3 + k, 1), w.get(0, 4 - k, 1));
+
+            // normal velocity is reversed, tangential velocity is mirrored
+            approx::assert_abs_diff_eq!(
+                w.get(IVX, 2, -k),
+                -w.get(IVX, 2, k - 1)
+            );
+            approx::assert_abs_diff_eq!(
+                w.get(IVY, 2, -k),
+                w.get(IVY, 2, k - 1)
+            );
+            approx::assert_abs_diff_eq!(
+                w.get(IVY, 3 + k, 1),
+                -w.get(IVY, 4 - k, 1)
+            );
+            approx::assert_abs_diff_eq!(
+                w.get(IVX, 3 + k, 1),
+                w.get(IVX, 4 - k, 1)
+            );
+        }
+    }
+
+    #[test]
+    fn test_outflow() {
+        let (mut w, coord) = setup();
+        BoundaryValues::new(BoundaryCondition::Outflow).apply(&mut w, &coord);
+
+        for n in 0..NHYDRO {
+            for k in 1..=NGHOST as i32 {
+                approx::assert_abs_diff_eq!(w.get(n, 1, -k), w.get(n, 1, 0));
+                approx::assert_abs_diff_eq!(w.get(n, 1, 4 + k), w.get(n, 1, 4));
+                approx::assert_abs_diff_eq!(w.get(n, -k, 3), w.get(n, 0, 3));
+                approx::assert_abs_diff_eq!(w.get(n, 3 + k, 3), w.get(n, 3, 3));
+            }
+        }
+    }
+
+    fn inflow(w: &mut Block2D<Real>, coord: &Coordinates, face: BoundaryFace) {
+        if face == BoundaryFace::InnerX1 {
+            for j in 0..coord.x2v.len() as i32 - 2 * NGHOST as i32 {
+                for k in 1..=NGHOST as i32 {
+                    *w.set(0, j, -k) = 2.0;
+                    *w.set(IVX, j, -k) = 0.5;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_user() {
+        let (mut w, coord) = setup();
+        let mut bvals = BoundaryValues::new(BoundaryCondition::Block);
+        bvals.set(BoundaryFace::InnerX1, BoundaryCondition::User(inflow));
+        bvals.apply(&mut w, &coord);
+
+        approx::assert_abs_diff_eq!(w.get(0, 3, -1), 2.0);
+        approx::assert_abs_diff_eq!(w.get(IVX, 0, -3), 0.5);
+
+        // untouched faces keep their values
+        let (w0, _) = setup();
+        approx::assert_abs_diff_eq!(w.get(0, 3, 5), w0.get(0, 3, 5));
+    }
+}
@@ -0,0 +1,528 @@
+//! Coordinates module
+//!
+//! Coordinates provides the following geometric quantities:
+//! 1. Cell-center and cell-face positions
+//! 2. Face areas normal to the x1 and x2 directions
+//! 3. Cell volumes
+//! 4. Geometric source terms of curvilinear grids
+//!
+//! Supported coordinate systems are (x1, x2) = (x, y) for Cartesian,
+//! (R, phi) for cylindrical and (r, theta) for spherical-polar grids. The
+//! extent of the third dimension is unity (Cartesian, cylindrical) or one
+//! radian (spherical-polar).
+//!
+//! The latitude-longitude grid (x1, x2) = (lambda, phi) describes the
+//! surface of a sphere of given radius rotating at angular velocity omega.
+//! It is used with the shallow-water system, where the first three
+//! components are the fluid height and the zonal and meridional velocities.
+//!
+//! The cubed-sphere grid (x1, x2) = (xi, eta) is one panel of the
+//! equiangular gnomonic projection (see gnomonic.rs). Velocities are stored
+//! along the unit basis vectors of the panel coordinate lines. The momentum
+//! components are the projections of the momentum onto the dual basis, so
+//! that the variation of the dual basis across a cell adds curvature terms
+//! to their source.
+//!
+//! Areas and volumes are stored in Block2D so that they can be iterated
+//! alongside the fluxes and the conserved variables.
+
+use itertools::{iproduct, izip};
+use crate::block::block2d::Block2D;
+use crate::coord::gnomonic;
+use crate::eos::{eos::EquationOfState, eos_trait};
+use crate::utils::{
+    common::add_comp,
+    defs::{Real, IDN, IVX, IVY, IVZ, NGHOST},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CoordinateSystem {
+    Cartesian,
+    Cylindrical,
+    SphericalPolar,
+    LatLon { radius: Real, omega: Real },
+    CubedSphere { radius: Real, omega: Real, panel: usize },
+}
+
+pub struct Coordinates {
+    pub system: CoordinateSystem,
+
+    // cell-face positions, including ghost cells
+    pub x1f: Vec<Real>,
+    pub x2f: Vec<Real>,
+
+    // cell-center positions, including ghost cells
+    pub x1v: Vec<Real>,
+    pub x2v: Vec<Real>,
+
+    // cell widths, including ghost cells
+    pub dx1f: Vec<Real>,
+    pub dx2f: Vec<Real>,
+
+    // face areas, area1(j, i) is the left x1-face of cell (j, i)
+    pub area1: Block2D<Real>,
+    pub area2: Block2D<Real>,
+
+    // cell volumes
+    pub vol: Block2D<Real>,
+}
+
+impl Coordinates {
+    /// Uniform grid covering [x2min, x2max] x [x1min, x1max]
+    pub fn new(
+        system: CoordinateSystem,
+        dim2: usize,
+        dim1: usize,
+        x2lim: (Real, Real),
+        x1lim: (Real, Real),
+    ) -> Self {
+        let x1f = faces(dim1, x1lim);
+        let x2f = faces(dim2, x2lim);
+        let x1v = centers(&x1f);
+        let x2v = centers(&x2f);
+        let dx1f: Vec<_> = x1f.windows(2).map(|x| x[1] - x[0]).collect();
+        let dx2f: Vec<_> = x2f.windows(2).map(|x| x[1] - x[0]).collect();
+
+        let mut area1 = Block2D::new(1, dim2, dim1, NGHOST);
+        let mut area2 = Block2D::new(1, dim2, dim1, NGHOST);
+        let mut vol = Block2D::new(1, dim2, dim1, NGHOST);
+
+        for j in 0..vol.len2 {
+            for i in 0..vol.len1 {
+                let (x1m, x1p) = (x1f[i], x1f[i + 1]);
+                let (x2m, x2p) = (x2f[j], x2f[j + 1]);
+                let index = j * vol.len1 + i;
+
+                let (a1, a2, v) = match system {
+                    CoordinateSystem::Cartesian => {
+                        (x2p - x2m, x1p - x1m, (x1p - x1m) * (x2p - x2m))
+                    },
+
+                    CoordinateSystem::Cylindrical => (
+                        x1m * (x2p - x2m),
+                        x1p - x1m,
+                        0.5 * (x1p * x1p - x1m * x1m) * (x2p - x2m),
+                    ),
+
+                    CoordinateSystem::SphericalPolar => (
+                        x1m * x1m * (x2m.cos() - x2p.cos()),
+                        0.5 * (x1p * x1p - x1m * x1m) * x2m.sin(),
+                        (x1p.powi(3) - x1m.powi(3)) / 3.0
+                            * (x2m.cos() - x2p.cos()),
+                    ),
+
+                    CoordinateSystem::LatLon { radius, .. } => (
+                        radius * (x2p - x2m),
+                        radius * x2m.cos() * (x1p - x1m),
+                        radius * radius * (x1p - x1m) * (x2p.sin() - x2m.sin()),
+                    ),
+
+                    CoordinateSystem::CubedSphere { radius, panel, .. } => (
+                        radius
+                            * gnomonic::arc_length(panel, (x1m, x2m), (x1m, x2p)),
+                        radius
+                            * gnomonic::arc_length(panel, (x1m, x2m), (x1p, x2m)),
+                        radius
+                            * radius
+                            * (gnomonic::corner_area(x1p, x2p)
+                                - gnomonic::corner_area(x1m, x2p)
+                                - gnomonic::corner_area(x1p, x2m)
+                                + gnomonic::corner_area(x1m, x2m)),
+                    ),
+                };
+
+                area1.data[index] = a1;
+                area2.data[index] = a2;
+                vol.data[index] = v;
+            }
+        }
+
+        Self {
+            system,
+            x1f,
+            x2f,
+            x1v,
+            x2v,
+            dx1f,
+            dx2f,
+            area1,
+            area2,
+            vol,
+        }
+    }
+
+    /// Add geometric source terms to the conserved variables
+    ///
+    /// The metric factors are derived from the face areas and cell volumes
+    /// so that a uniform pressure is in exact balance with the flux
+    /// divergence.
+    pub fn add_coordinate_source<E: eos_trait::EquationOfState>(
+        &self,
+        eos: &mut EquationOfState<E>,
+        dt: Real,
+    ) {
+        let [idn, iv1, iv2, iv3] = [IDN, IVX, IVY, IVZ].map(|n| eos.comps[n]);
+
+        let u = eos.u.interior_mut();
+        let w = eos.w.interior();
+        let area1 = self.area1.interior();
+        let area1_p1 = self.area1.interior_shift(0, 1);
+        let area2 = self.area2.interior();
+        let area2_p1 = self.area2.interior_shift(1, 0);
+        let vol = self.vol.interior();
+
+        // cell-face positions of the interior cells
+        let x2f = &self.x2f[NGHOST..self.x2f.len() - NGHOST];
+        let x1f = &self.x1f[NGHOST..self.x1f.len() - NGHOST];
+        let xf = iproduct!(x2f.windows(2), x1f.windows(2));
+
+        for (u, w, area1, area1_p1, area2, area2_p1, vol, (x2f, x1f)) in
+            izip!(u, w, area1, area1_p1, area2, area2_p1, vol, xf)
+        {
+            let (x1m, x1p, x2m, x2p) = (x1f[0], x1f[1], x2f[0], x2f[1]);
+            let x1v = 0.5 * (x1m + x1p);
+            let x2v = 0.5 * (x2m + x2p);
+
+            let (rho, pres) = (w[idn], eos.law.pressure(w, &eos.comps));
+            let (v1, v2, v3) = (w[iv1], w[iv2], w[iv3]);
+
+            // (A_{+} - A_{-}) / V
+            let fac1 = (area1_p1[0] - area1[0]) / vol[0];
+            let fac2 = (area2_p1[0] - area2[0]) / vol[0];
+
+            unsafe {
+                match self.system {
+                    CoordinateSystem::Cartesian => {},
+
+                    // fac1 = 1/R
+                    CoordinateSystem::Cylindrical => {
+                        add_comp(u, iv1, dt * fac1 * (pres + rho * v2 * v2));
+                        add_comp(u, iv2, -dt * fac1 * rho * v1 * v2);
+                    },
+
+                    // fac1 = 2/r, fac2 = cot(theta)/r
+                    CoordinateSystem::SphericalPolar => {
+                        let rinv = 0.5 * fac1;
+                        add_comp(
+                            u,
+                            iv1,
+                            dt * (fac1 * pres
+                                + rinv * rho * (v2 * v2 + v3 * v3)),
+                        );
+                        add_comp(
+                            u,
+                            iv2,
+                            dt * (fac2 * (pres + rho * v3 * v3)
+                                - rinv * rho * v1 * v2),
+                        );
+                        add_comp(
+                            u,
+                            iv3,
+                            -dt * rho * v3 * (rinv * v1 + fac2 * v2),
+                        );
+                    },
+
+                    // shallow water, fac2 = -tan(phi)/a
+                    CoordinateSystem::LatLon { omega, .. } => {
+                        let h = rho;
+                        let cor = 2.0 * omega * x2v.sin() - fac2 * v1;
+                        add_comp(u, iv1, dt * cor * h * v2);
+                        add_comp(u, iv2, dt * (fac2 * pres - cor * h * v1));
+                    },
+
+                    // shallow water, the momentum flux T = h v v + p I
+                    // projected onto the dual basis e^a leaves the source
+                    // T : grad e^a = h v . (v . grad) e^a + p div e^a, and
+                    // the Coriolis force -f r x v
+                    CoordinateSystem::CubedSphere {
+                        radius,
+                        omega,
+                        panel,
+                    } => {
+                        let h = rho;
+                        let r = gnomonic::to_cartesian(panel, x1v, x2v);
+                        let e = gnomonic::basis(panel, x1v, x2v);
+                        let v = gnomonic::from_components(&e, &[v1, v2]);
+                        let cor = gnomonic::to_components(
+                            &e,
+                            &gnomonic::cross(&r, &v),
+                        );
+                        let f = 2.0 * omega * r[2];
+
+                        // div e^a from the dual basis on the faces, where
+                        // e^1 . n1 = 1 / sin and e^2 . n1 = -g12 / sin, so
+                        // that a uniform pressure balances the fluxes
+                        let face = |xi: Real, eta: Real, area: Real| {
+                            let g12 = gnomonic::cos_angle(xi, eta);
+                            let sin = (1.0 - g12 * g12).sqrt();
+                            (area / sin, -g12 * area / sin)
+                        };
+                        let (a1p, b1p) = face(x1p, x2v, area1_p1[0]);
+                        let (a1m, b1m) = face(x1m, x2v, area1[0]);
+                        let (a2p, b2p) = face(x1v, x2p, area2_p1[0]);
+                        let (a2m, b2m) = face(x1v, x2m, area2[0]);
+                        let div1 = (a1p - a1m + b2p - b2m) / vol[0];
+                        let div2 = (a2p - a2m + b1p - b1m) / vol[0];
+
+                        // (v . grad) e^a . v across the cell, with v fixed
+                        let dual = |xi: Real, eta: Real| {
+                            gnomonic::to_components(
+                                &gnomonic::basis(panel, xi, eta),
+                                &v,
+                            )
+                        };
+                        let (d1p, d1m) = (dual(x1p, x2v), dual(x1m, x2v));
+                        let (d2p, d2m) = (dual(x1v, x2p), dual(x1v, x2m));
+                        let l1 = radius
+                            * gnomonic::arc_length(
+                                panel,
+                                (x1m, x2v),
+                                (x1p, x2v),
+                            );
+                        let l2 = radius
+                            * gnomonic::arc_length(
+                                panel,
+                                (x1v, x2m),
+                                (x1v, x2p),
+                            );
+                        let curv = |a: usize| {
+                            h * (v1 * (d1p[a] - d1m[a]) / l1
+                                + v2 * (d2p[a] - d2m[a]) / l2)
+                        };
+
+                        add_comp(
+                            u,
+                            iv1,
+                            dt * (div1 * pres + curv(0) - f * h * cor[0]),
+                        );
+                        add_comp(
+                            u,
+                            iv2,
+                            dt * (div2 * pres + curv(1) - f * h * cor[1]),
+                        );
+                    },
+                }
+            }
+        }
+    }
+}
+
+/// Uniformly spaced cell faces, extended by NGHOST cells on each side
+fn faces(dim: usize, xlim: (Real, Real)) -> Vec<Real> {
+    let dx = (xlim.1 - xlim.0) / dim as Real;
+    (0..=dim + 2 * NGHOST)
+        .map(|i| xlim.0 + (i as Real - NGHOST as Real) * dx)
+        .collect()
+}
+
+fn centers(xf: &[Real]) -> Vec<Real> {
+    xf.windows(2).map(|x| 0.5 * (x[0] + x[1])).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eos::{ideal_gas::IdealGas, shallow_water::ShallowWater};
+    use crate::hydro::hydro::Hydro;
+    use crate::reconstruct::reconstruction::Reconstruction;
+    use crate::utils::{common::set_comp, defs::IPR};
+
+    #[test]
+    fn test_cartesian() {
+        let coord = Coordinates::new(
+            CoordinateSystem::Cartesian,
+            4,
+            5,
+            (0.0, 2.0),
+            (0.0, 1.0),
+        );
+
+        assert_eq!(coord.x1f.len(), 5 + 2 * NGHOST + 1);
+        assert_eq!(coord.x1v.len(), 5 + 2 * NGHOST);
+        approx::assert_abs_diff_eq!(coord.x1f[NGHOST], 0.0);
+        approx::assert_abs_diff_eq!(coord.x2f[4 + NGHOST], 2.0);
+        approx::assert_abs_diff_eq!(coord.x1v[NGHOST], 0.1);
+        approx::assert_abs_diff_eq!(coord.dx2f[NGHOST], 0.5);
+
+        for (a1, a2, vol) in izip!(
+            coord.area1.interior(),
+            coord.area2.interior(),
+            coord.vol.interior()
+        ) {
+            approx::assert_abs_diff_eq!(a1[0], 0.5, epsilon = 1e-14);
+            approx::assert_abs_diff_eq!(a2[0], 0.2, epsilon = 1e-14);
+            approx::assert_abs_diff_eq!(vol[0], 0.1, epsilon = 1e-14);
+        }
+    }
+
+    #[test]
+    fn test_total_volume() {
+        let pi = std::f64::consts::PI;
+
+        // half annulus 1 < R < 2, 0 < phi < pi
+        let coord = Coordinates::new(
+            CoordinateSystem::Cylindrical,
+            8,
+            6,
+            (0.0, pi),
+            (1.0, 2.0),
+        );
+        let total: Real = coord.vol.interior().map(|v| v[0]).sum();
+        approx::assert_abs_diff_eq!(total, 1.5 * pi, epsilon = 1e-12);
+
+        // spherical shell 1 < r < 2, 0 < theta < pi, per radian in phi
+        let coord = Coordinates::new(
+            CoordinateSystem::SphericalPolar,
+            8,
+            6,
+            (0.0, pi),
+            (1.0, 2.0),
+        );
+        let total: Real = coord.vol.interior().map(|v| v[0]).sum();
+        approx::assert_abs_diff_eq!(total, 14.0 / 3.0, epsilon = 1e-12);
+    }
+
+    /// A fluid at rest with uniform pressure remains at rest: the pressure
+    /// flux through the faces is balanced by the geometric source term.
+    #[test]
+    fn test_uniform_pressure_balance() {
+        let (dim2, dim1) = (6, 5);
+        let pres = 2.5;
+
+        for system in [
+            CoordinateSystem::Cartesian,
+            CoordinateSystem::Cylindrical,
+            CoordinateSystem::SphericalPolar,
+        ] {
+            let coord =
+                Coordinates::new(system, dim2, dim1, (0.3, 2.8), (1.0, 3.0));
+            let mut hydro = Hydro::new(dim2, dim1);
+            let mut eos = EquationOfState::new(dim2, dim1, IdealGas::default());
+
+            for w in eos.w.all_mut() {
+                unsafe {
+                    set_comp(w, eos.comps[IDN], 1.0);
+                    set_comp(w, eos.comps[IPR], pres);
+                }
+            }
+            eos.primitive_to_conserved();
+
+            // pressure flux normal to each face
+            let iv1 = hydro.comps[IVX];
+            let iv2 = hydro.comps[IVY];
+            for flx in hydro.flx[Hydro::X1DIR].all_mut() {
+                unsafe { set_comp(flx, iv1, pres) };
+            }
+            for flx in hydro.flx[Hydro::X2DIR].all_mut() {
+                unsafe { set_comp(flx, iv2, pres) };
+            }
+
+            hydro.add_flux_divergence(&mut eos, &coord, 0.1);
+            coord.add_coordinate_source(&mut eos, 0.1);
+
+            for u in eos.u.interior() {
+                approx::assert_abs_diff_eq!(u[iv1], 0.0, epsilon = 1e-12);
+                approx::assert_abs_diff_eq!(u[iv2], 0.0, epsilon = 1e-12);
+            }
+        }
+    }
+
+    /// Tendency of the meridional momentum for Williamson et al. (1992)
+    /// test case 2, a steady zonal flow in geostrophic balance
+    fn williamson2_residual(dim: usize) -> Real {
+        let pi = std::f64::consts::PI;
+        let (radius, omega, grav) = (1.0, 1.0, 2.0);
+        let (h0, u0) = (2.0, 0.2);
+
+        let coord = Coordinates::new(
+            CoordinateSystem::LatLon { radius, omega },
+            dim,
+            2 * dim,
+            (-pi / 3.0, pi / 3.0),
+            (0.0, 2.0 * pi),
+        );
+        let mut hydro = Hydro::new(dim, 2 * dim);
+        let mut eos = EquationOfState::new(dim, 2 * dim, ShallowWater { grav });
+
+        for (w, x2) in eos.w.all_mut().zip(
+            coord
+                .x2v
+                .iter()
+                .flat_map(|x2| std::iter::repeat_n(*x2, coord.x1v.len())),
+        ) {
+            let h = h0
+                - (radius * omega * u0 + 0.5 * u0 * u0) * x2.sin().powi(2)
+                    / grav;
+            unsafe {
+                set_comp(w, eos.comps[IDN], h);
+                set_comp(w, eos.comps[IVX], u0 * x2.cos());
+                set_comp(w, eos.comps[IVY], 0.0);
+            }
+        }
+
+        hydro.reconstruct_x1(&eos);
+        hydro.reconstruct_x2(&eos);
+        unsafe {
+            hydro.riemann_solver_x1(&eos, &coord);
+            hydro.riemann_solver_x2(&eos, &coord);
+        }
+
+        // tendency of the conserved variables
+        eos.u.data.fill(0.0);
+        hydro.add_flux_divergence(&mut eos, &coord, 1.0);
+        coord.add_coordinate_source(&mut eos, 1.0);
+
+        let iv2 = eos.comps[IVY];
+        eos.u.interior().map(|u| u[iv2].abs()).fold(0.0, Real::max)
+    }
+
+    #[test]
+    fn test_latlon_geostrophic_balance() {
+        let coarse = williamson2_residual(12);
+        let fine = williamson2_residual(24);
+
+        assert!(coarse < 5e-3, "residual = {}", coarse);
+        assert!(fine < 0.25 * coarse, "{} vs {}", fine, coarse);
+    }
+
+    #[test]
+    fn test_latlon_lake_at_rest() {
+        let pi = std::f64::consts::PI;
+        let (dim2, dim1) = (8, 16);
+        let coord = Coordinates::new(
+            CoordinateSystem::LatLon {
+                radius: 2.0,
+                omega: 0.5,
+            },
+            dim2,
+            dim1,
+            (-0.4 * pi, 0.4 * pi),
+            (0.0, 2.0 * pi),
+        );
+        let mut hydro = Hydro::new(dim2, dim1);
+        let mut eos =
+            EquationOfState::new(dim2, dim1, ShallowWater { grav: 9.8 });
+
+        for w in eos.w.all_mut() {
+            unsafe { set_comp(w, eos.comps[IDN], 3.0) };
+        }
+
+        hydro.recon = Reconstruction::Donor;
+        hydro.reconstruct_x1(&eos);
+        hydro.reconstruct_x2(&eos);
+        unsafe {
+            hydro.riemann_solver_x1(&eos, &coord);
+            hydro.riemann_solver_x2(&eos, &coord);
+        }
+
+        eos.u.data.fill(0.0);
+        hydro.add_flux_divergence(&mut eos, &coord, 1.0);
+        coord.add_coordinate_source(&mut eos, 1.0);
+
+        for u in eos.u.interior() {
+            for n in &eos.comps[IDN..=IVY] {
+                approx::assert_abs_diff_eq!(u[*n], 0.0, epsilon = 1e-12);
+            }
+        }
+    }
+}
@@ -0,0 +1,181 @@
+//! Equiangular gnomonic projection of the cubed sphere
+//!
+//! The sphere is covered by six panels. Panels 0-3 straddle the equator,
+//! panel 4 covers the north pole and panel 5 the south pole. Each panel
+//! uses the local angles (xi, eta) in [-pi/4, pi/4] and the point
+//! n + tan(xi) ex + tan(eta) ey projected onto the unit sphere, where
+//! (n, ex, ey) is the right-handed frame of the panel.
+//!
+//! Vectors tangent to the sphere are stored by their components along the
+//! unit (non-orthogonal) basis vectors of constant eta and constant xi.
+
+use crate::utils::defs::Real;
+
+pub type Vec3 = [Real; 3];
+
+pub const NPANEL: usize = 6;
+
+/// Frame (n, ex, ey) of each panel
+const FRAMES: [[Vec3; 3]; NPANEL] = [
+    [[1., 0., 0.], [0., 1., 0.], [0., 0., 1.]],
+    [[0., 1., 0.], [-1., 0., 0.], [0., 0., 1.]],
+    [[-1., 0., 0.], [0., -1., 0.], [0., 0., 1.]],
+    [[0., -1., 0.], [1., 0., 0.], [0., 0., 1.]],
+    [[0., 0., 1.], [0., 1., 0.], [-1., 0., 0.]],
+    [[0., 0., -1.], [0., 1., 0.], [1., 0., 0.]],
+];
+
+pub fn dot(a: &Vec3, b: &Vec3) -> Real {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+pub fn cross(a: &Vec3, b: &Vec3) -> Vec3 {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn normalize(a: Vec3) -> Vec3 {
+    let norm = dot(&a, &a).sqrt();
+    [a[0] / norm, a[1] / norm, a[2] / norm]
+}
+
+/// Point on the unit sphere at the panel angles (xi, eta)
+pub fn to_cartesian(panel: usize, xi: Real, eta: Real) -> Vec3 {
+    let [n, ex, ey] = FRAMES[panel];
+    let (x, y) = (xi.tan(), eta.tan());
+    normalize([
+        n[0] + x * ex[0] + y * ey[0],
+        n[1] + x * ex[1] + y * ey[1],
+        n[2] + x * ex[2] + y * ey[2],
+    ])
+}
+
+/// Panel angles (xi, eta) of a point, extended beyond the panel edges
+pub fn from_cartesian(panel: usize, r: &Vec3) -> (Real, Real) {
+    let [n, ex, ey] = FRAMES[panel];
+    let rn = dot(r, &n);
+    ((dot(r, &ex) / rn).atan(), (dot(r, &ey) / rn).atan())
+}
+
+/// Panel that contains a point
+pub fn find_panel(r: &Vec3) -> usize {
+    (0..NPANEL)
+        .max_by(|a, b| {
+            dot(r, &FRAMES[*a][0]).total_cmp(&dot(r, &FRAMES[*b][0]))
+        })
+        .unwrap()
+}
+
+/// Unit tangent vectors along the xi and eta coordinate lines
+pub fn basis(panel: usize, xi: Real, eta: Real) -> [Vec3; 2] {
+    let [_, ex, ey] = FRAMES[panel];
+    let r = to_cartesian(panel, xi, eta);
+
+    let tangent = |e: Vec3| {
+        let re = dot(&r, &e);
+        normalize([e[0] - re * r[0], e[1] - re * r[1], e[2] - re * r[2]])
+    };
+
+    [tangent(ex), tangent(ey)]
+}
+
+/// Cosine of the angle between the xi and eta coordinate lines, the same
+/// on all panels
+pub fn cos_angle(xi: Real, eta: Real) -> Real {
+    -xi.sin() * eta.sin()
+}
+
+/// Components of a tangent vector along a (non-orthogonal) unit basis
+pub fn to_components(basis: &[Vec3; 2], v: &Vec3) -> [Real; 2] {
+    let g12 = dot(&basis[0], &basis[1]);
+    let (b1, b2) = (dot(v, &basis[0]), dot(v, &basis[1]));
+    let det = 1.0 - g12 * g12;
+    [(b1 - g12 * b2) / det, (b2 - g12 * b1) / det]
+}
+
+/// Tangent vector from its components along a unit basis
+pub fn from_components(basis: &[Vec3; 2], c: &[Real; 2]) -> Vec3 {
+    [
+        c[0] * basis[0][0] + c[1] * basis[1][0],
+        c[0] * basis[0][1] + c[1] * basis[1][1],
+        c[0] * basis[0][2] + c[1] * basis[1][2],
+    ]
+}
+
+/// Area on the unit sphere of the panel region [0, xi] x [0, eta]
+pub fn corner_area(xi: Real, eta: Real) -> Real {
+    let (x, y) = (xi.tan(), eta.tan());
+    (x * y / (1.0 + x * x + y * y).sqrt()).atan()
+}
+
+/// Great-circle distance on the unit sphere between two panel points
+pub fn arc_length(panel: usize, p: (Real, Real), q: (Real, Real)) -> Real {
+    let a = to_cartesian(panel, p.0, p.1);
+    let b = to_cartesian(panel, q.0, q.1);
+    dot(&a, &b).clamp(-1.0, 1.0).acos()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::FRAC_PI_4;
+
+    #[test]
+    fn test_round_trip() {
+        for panel in 0..NPANEL {
+            let r = to_cartesian(panel, 0.3, -0.5);
+            let (xi, eta) = from_cartesian(panel, &r);
+
+            assert_eq!(find_panel(&r), panel);
+            approx::assert_abs_diff_eq!(xi, 0.3, epsilon = 1e-14);
+            approx::assert_abs_diff_eq!(eta, -0.5, epsilon = 1e-14);
+        }
+    }
+
+    #[test]
+    fn test_panel_edges_match() {
+        // the east edge of panel 0 is the west edge of panel 1
+        let r = to_cartesian(0, FRAC_PI_4, 0.2);
+        let (xi, eta) = from_cartesian(1, &r);
+        approx::assert_abs_diff_eq!(xi, -FRAC_PI_4, epsilon = 1e-14);
+        approx::assert_abs_diff_eq!(eta, 0.2, epsilon = 1e-14);
+    }
+
+    #[test]
+    fn test_components() {
+        let e = basis(4, 0.4, 0.6);
+        let v = from_components(&e, &[1.5, -0.5]);
+
+        approx::assert_abs_diff_eq!(dot(&v, &to_cartesian(4, 0.4, 0.6)), 0.0);
+        let c = to_components(&e, &v);
+        approx::assert_abs_diff_eq!(c[0], 1.5, epsilon = 1e-14);
+        approx::assert_abs_diff_eq!(c[1], -0.5, epsilon = 1e-14);
+    }
+
+    #[test]
+    fn test_cos_angle() {
+        assert_eq!(cos_angle(0.0, 0.7), 0.0);
+        for panel in 0..NPANEL {
+            let [e1, e2] = basis(panel, 0.4, -0.6);
+            approx::assert_abs_diff_eq!(
+                cos_angle(0.4, -0.6),
+                dot(&e1, &e2),
+                epsilon = 1e-15
+            );
+        }
+        assert!(cos_angle(0.4, 0.6) < 0.0);
+    }
+
+    #[test]
+    fn test_panel_area() {
+        let area = 4.0 * corner_area(FRAC_PI_4, FRAC_PI_4);
+        approx::assert_abs_diff_eq!(
+            NPANEL as Real * area,
+            4.0 * std::f64::consts::PI,
+            epsilon = 1e-14
+        );
+    }
+}
@@ -0,0 +1,2 @@
+pub mod coordinates;
+pub mod gnomonic;
@@ -3,14 +3,20 @@
 //! Equation of state provides the following functions:
 //! 1. Convert conserved variables to primitive variables
 //! 2. Convert primitive variables to conserved variables
+//!
+//! The conversions of each cell are delegated to an implementation of
+//! eos_trait::EquationOfState (ideal gas, isothermal, polytropic or
+//! shallow water).
 
 use crate::block::block2d::Block2D;
-use crate::utils::{
-    common::set_comp,
-    defs::{Real, NGHOST, NHYDRO},
-};
+use crate::eos::{eos_trait, ideal_gas::IdealGas};
+use itertools::iproduct;
+use crate::utils::defs::{Real, NGHOST, NHYDRO};
+
+pub struct EquationOfState<E: eos_trait::EquationOfState = IdealGas> {
+    // equation of state of each cell
+    pub law: E,
 
-pub struct EquationOfState {
     // components
     pub comps: [usize; NHYDRO],
 
@@ -20,13 +26,17 @@ pub struct EquationOfState {
     // conserved variables
     pub u: Block2D<Real>,
 
-    // conserved variable register
-    _u1: Block2D<Real>,
-    _u2: Block2D<Real>,
+    // conserved variable registers
+    pub u1: Block2D<Real>,
+    pub u2: Block2D<Real>,
+
+    // bottom elevation of a shallow-water layer, None for a flat bottom
+    pub zb: Option<Block2D<Real>>,
 }
 
-impl EquationOfState {
-    pub fn new(dim2: usize, dim1: usize) -> Self {
+impl<E: eos_trait::EquationOfState> EquationOfState<E> {
+    /// Variables on dim2 x dim1 cells closed by the equation of state law
+    pub fn new(dim2: usize, dim1: usize, law: E) -> Self {
         let w = Block2D::new(NHYDRO, dim2, dim1, NGHOST);
         let u = Block2D::new(NHYDRO, dim2, dim1, NGHOST);
 
@@ -39,37 +49,39 @@ impl EquationOfState {
         }
 
         Self {
+            law,
             comps,
             w,
             u,
-            _u1: u1,
-            _u2: u2,
+            u1,
+            u2,
+            zb: None,
         }
     }
 
+    /// Bottom elevation of a shallow-water layer, allocated flat on first
+    /// use. Only the interior needs to be filled, the ghost cells are filled
+    /// with the primitive variables by the boundary conditions and the ghost
+    /// exchange of the Mesh. With a bottom the hydrodynamics switches to the
+    /// hydrostatic reconstruction.
+    pub fn bottom(&mut self) -> &mut Block2D<Real> {
+        let (_, len2, len1) = self.w.shape();
+        let nghost = self.w.nghost;
+        self.zb.get_or_insert_with(|| {
+            Block2D::new(1, len2 - 2 * nghost, len1 - 2 * nghost, nghost)
+        })
+    }
+
     /// # Safety
     ///
     /// This function may be unsafe because it dereferences a raw pointer.
     pub fn conserved_to_primitive(&mut self) {
         let w = self.w.all_mut();
         let u = self.u.all();
-        let gm1 = 0.4;
-
-        let [idn, iv1, iv2, iv3, ipr] = self.comps;
 
         for (w, u) in w.zip(u) {
-            let rho = u[idn];
-            let vx = u[iv1] / rho;
-            let vy = u[iv2] / rho;
-            let vz = u[iv3] / rho;
-            let ke = 0.5 * rho * (vx * vx + vy * vy + vz * vz);
-
             unsafe {
-                set_comp(w, idn, rho);
-                set_comp(w, iv1, vx);
-                set_comp(w, iv2, vy);
-                set_comp(w, iv3, vz);
-                set_comp(w, ipr, gm1 * (u[ipr] - ke));
+                self.law.conserved_to_primitive(w, u, &self.comps);
             }
         }
     }
@@ -85,28 +97,10 @@ impl EquationOfState {
     pub fn primitive_to_conserved(&mut self) {
         let w = self.w.all();
         let u = self.u.all_mut();
-        let gm1 = 0.4;
-
-        let idn = self.w.icomp(0);
-        let iv1 = self.w.icomp(1);
-        let iv2 = self.w.icomp(2);
-        let iv3 = self.w.icomp(3);
-        let ipr = self.w.icomp(4);
 
         for (w, u) in w.zip(u) {
-            let rho = w[idn];
-            let vx = w[iv1];
-            let vy = w[iv2];
-            let vz = w[iv3];
-            let ke = 0.5 * rho * (vx * vx + vy * vy + vz * vz);
-            let ie = w[ipr] / gm1;
-
             unsafe {
-                set_comp(u, idn, rho);
-                set_comp(u, iv1, rho * vx);
-                set_comp(u, iv2, rho * vy);
-                set_comp(u, iv3, rho * vz);
-                set_comp(u, ipr, ke + ie);
+                self.law.primitive_to_conserved(u, w, &self.comps);
             }
         }
     }
@@ -115,9 +109,52 @@ impl EquationOfState {
         let overhead = 1.0;
         (self.w.size() as Real) * (self.u.size() as Real) * overhead
     }
+
+    /// Saturation adjustment of the interior cells by the law, called by
+    /// Mesh::step at the end of each step. The conserved variables of the
+    /// adjusted cells are updated from their primitive variables.
+    pub fn saturation_adjustment(&mut self) {
+        let local: [usize; NHYDRO] = std::array::from_fn(|n| n);
+        let nx2 = (self.w.len2 - 2 * self.w.nghost) as i32;
+        let nx1 = (self.w.len1 - 2 * self.w.nghost) as i32;
+
+        for (j, i) in iproduct!(0..nx2, 0..nx1) {
+            let mut w = [0.0; NHYDRO];
+            for (n, w) in w.iter_mut().enumerate() {
+                *w = self.w.get(n, j, i);
+            }
+            if !self.law.saturation_adjustment(&mut w, &local) {
+                continue;
+            }
+
+            let mut u = [0.0; NHYDRO];
+            unsafe {
+                self.law.primitive_to_conserved(u.as_mut_ptr(), &w, &local);
+            }
+            for (n, (w, u)) in w.iter().zip(u).enumerate() {
+                *self.w.set(n, j, i) = *w;
+                *self.u.set(n, j, i) = u;
+            }
+        }
+    }
+
+    /// Pressure of a cell of primitive variables
+    pub fn pressure(&self, w: &[Real]) -> Real {
+        self.law.pressure(w, &self.comps)
+    }
+
+    /// Sound speed of a cell of primitive variables
+    pub fn sound_speed(&self, w: &[Real]) -> Real {
+        self.law.sound_speed(w, &self.comps)
+    }
+
+    /// Total energy per unit volume of a cell of primitive variables
+    pub fn energy(&self, w: &[Real]) -> Real {
+        self.law.energy(w, &self.comps)
+    }
 }
 
-impl Default for EquationOfState {
+impl<E: eos_trait::EquationOfState> Default for EquationOfState<E> {
     fn default() -> Self {
         let w = Block2D::new(0, 0, 0, 0);
         let u = Block2D::new(0, 0, 0, 0);
@@ -127,11 +164,13 @@ impl Default for EquationOfState {
 
         let comps = [0; NHYDRO];
         Self {
+            law: E::default(),
             comps,
             w,
             u,
-            _u1: u1,
-            _u2: u2,
+            u1,
+            u2,
+            zb: None,
         }
     }
 }
@@ -139,10 +178,16 @@ impl Default for EquationOfState {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::eos::{isothermal::Isothermal, shallow_water::ShallowWater};
+    use crate::utils::{
+        common::dry_state,
+        defs::{IDN, IPR, IVX, IVY, IVZ},
+    };
+    use itertools::iproduct;
 
     #[test]
     fn test_new() {
-        let eos = EquationOfState::new(20, 10);
+        let eos = EquationOfState::new(20, 10, IdealGas::default());
 
         assert_eq!(eos.w.shape(), (NHYDRO, 20 + 2 * NGHOST, 10 + 2 * NGHOST));
         assert_eq!(eos.u.shape(), (NHYDRO, 20 + 2 * NGHOST, 10 + 2 * NGHOST));
@@ -150,17 +195,17 @@ mod tests {
 
     #[test]
     fn test_conserved_to_primitive() {
-        let mut eos = EquationOfState::new(3, 3);
+        let mut eos = EquationOfState::new(3, 3, IdealGas::default());
         eos.u.data.fill(1.0);
 
         eos.conserved_to_primitive();
 
         // variable indices
-        let idn = eos.w.icomp(0);
-        let iv1 = eos.w.icomp(1);
-        let iv2 = eos.w.icomp(2);
-        let iv3 = eos.w.icomp(3);
-        let ipr = eos.w.icomp(4);
+        let idn = eos.w.icomp(IDN);
+        let iv1 = eos.w.icomp(IVX);
+        let iv2 = eos.w.icomp(IVY);
+        let iv3 = eos.w.icomp(IVZ);
+        let ipr = eos.w.icomp(IPR);
 
         for (w, u) in eos.w.all().zip(eos.u.all()) {
             let rho = w[idn];
@@ -168,7 +213,7 @@ mod tests {
             let vy = w[iv2];
             let vz = w[iv3];
             let ke = 0.5 * rho * (vx * vx + vy * vy + vz * vz);
-            let pr = gm1() * (u[ipr] - ke);
+            let pr = (eos.law.gamma - 1.0) * (u[ipr] - ke);
 
             approx::assert_abs_diff_eq!(w[idn], rho);
             approx::assert_abs_diff_eq!(w[iv1], vx);
@@ -180,17 +225,17 @@ mod tests {
 
     #[test]
     fn test_primitive_to_conserved() {
-        let mut eos = EquationOfState::new(3, 3);
+        let mut eos = EquationOfState::new(3, 3, IdealGas::default());
         eos.w.data.fill(1.0);
 
         eos.primitive_to_conserved();
 
         // variable indices
-        let idn = eos.w.icomp(0);
-        let iv1 = eos.w.icomp(1);
-        let iv2 = eos.w.icomp(2);
-        let iv3 = eos.w.icomp(3);
-        let ipr = eos.w.icomp(4);
+        let idn = eos.w.icomp(IDN);
+        let iv1 = eos.w.icomp(IVX);
+        let iv2 = eos.w.icomp(IVY);
+        let iv3 = eos.w.icomp(IVZ);
+        let ipr = eos.w.icomp(IPR);
 
         for (w, u) in eos.w.all().zip(eos.u.all()) {
             let rho = w[idn];
@@ -198,7 +243,7 @@ mod tests {
             let vy = w[iv2];
             let vz = w[iv3];
             let ke = 0.5 * rho * (vx * vx + vy * vy + vz * vz);
-            let ie = w[ipr] / gm1();
+            let ie = w[ipr] / (eos.law.gamma - 1.0);
 
             approx::assert_abs_diff_eq!(u[idn], rho);
             approx::assert_abs_diff_eq!(u[iv1], rho * vx);
@@ -208,7 +253,62 @@ mod tests {
         }
     }
 
-    fn gm1() -> Real {
-        0.4
+    #[test]
+    fn test_sound_speed() {
+        let mut eos = EquationOfState::new(3, 3, IdealGas::default());
+        eos.w.data.fill(1.0);
+
+        for w in eos.w.interior() {
+            approx::assert_abs_diff_eq!(eos.sound_speed(w), 1.4_f64.sqrt());
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let w0 = dry_state([1.2, 0.3, -0.4, 0.5, 2.0]);
+
+        for gamma in [5.0 / 3.0, 1.4, 1.3, 1.1] {
+            let mut eos = EquationOfState::new(3, 3, IdealGas { gamma });
+            eos.w.data.fill(1.0);
+            for (n, j, i) in iproduct!(0..NHYDRO, 0..3, 0..3) {
+                *eos.w.set(n, j, i) = w0[n];
+            }
+
+            eos.primitive_to_conserved();
+            eos.w.data.fill(0.0);
+            eos.conserved_to_primitive();
+
+            for (n, j, i) in iproduct!(0..NHYDRO, 0..3, 0..3) {
+                approx::assert_abs_diff_eq!(
+                    eos.w.get(n, j, i),
+                    w0[n],
+                    epsilon = 1e-14
+                );
+            }
+
+            let w = eos.w.interior().next().unwrap();
+            approx::assert_abs_diff_eq!(
+                eos.sound_speed(w),
+                (gamma * w0[IPR] / w0[IDN]).sqrt()
+            );
+        }
+    }
+
+    #[test]
+    fn test_barotropic_pressure() {
+        let mut iso = EquationOfState::new(3, 3, Isothermal { cs: 0.5 });
+        let mut sw = EquationOfState::new(3, 3, ShallowWater { grav: 2.0 });
+        iso.u.data.fill(2.0);
+        sw.u.data.fill(2.0);
+
+        iso.conserved_to_primitive();
+        sw.conserved_to_primitive();
+
+        for (w_iso, w_sw) in iso.w.interior().zip(sw.w.interior()) {
+            approx::assert_abs_diff_eq!(w_iso[iso.comps[IPR]], 0.5);
+            approx::assert_abs_diff_eq!(iso.pressure(w_iso), 0.5);
+            approx::assert_abs_diff_eq!(w_sw[sw.comps[IPR]], 4.0);
+            approx::assert_abs_diff_eq!(sw.sound_speed(w_sw), 2.0);
+        }
     }
 }
@@ -1,5 +1,111 @@
+//! Equation of state trait
+//!
+//! An equation of state closes the hydrodynamic equations cell by cell. The
+//! cell variables are accessed through the component offsets comps, in the
+//! order (density, velocity 1, velocity 2, velocity 3, pressure) for the
+//! primitive variables and (density, momentum 1, momentum 2, momentum 3,
+//! energy) for the conserved variables.
 
-pub trait EquationOfState {
-    fn primitive_to_conserved(&mut self);
-    fn conserved_to_primitive(&mut self);
+use crate::utils::defs::{Real, IDN, IPR, IVX, IVY, IVZ, NHYDRO};
+
+/// Square matrix over the components of a cell, indexed by component
+/// numbers
+pub type Matrix = [[Real; NHYDRO]; NHYDRO];
+
+pub trait EquationOfState: Clone + Default {
+    /// # Safety
+    ///
+    /// Write the conserved variables of the primitive cell w to the raw
+    /// pointer u.
+    unsafe fn primitive_to_conserved(
+        &self,
+        u: *mut Real,
+        w: &[Real],
+        comps: &[usize; NHYDRO],
+    );
+
+    /// # Safety
+    ///
+    /// Write the primitive variables of the conserved cell u to the raw
+    /// pointer w.
+    unsafe fn conserved_to_primitive(
+        &self,
+        w: *mut Real,
+        u: &[Real],
+        comps: &[usize; NHYDRO],
+    );
+
+    /// Pressure of a cell of primitive variables
+    fn pressure(&self, w: &[Real], comps: &[usize; NHYDRO]) -> Real;
+
+    /// Sound speed of a cell of primitive variables
+    fn sound_speed(&self, w: &[Real], comps: &[usize; NHYDRO]) -> Real;
+
+    /// Total energy per unit volume of a cell of primitive variables
+    fn energy(&self, w: &[Real], comps: &[usize; NHYDRO]) -> Real;
+
+    /// Bring a cell of primitive variables to phase equilibrium, returning
+    /// whether it changed. The default has no phase changes.
+    fn saturation_adjustment(
+        &self,
+        _w: &mut [Real],
+        _comps: &[usize; NHYDRO],
+    ) -> bool {
+        false
+    }
+
+    /// Left and right eigenvectors of the primitive equations along the
+    /// velocity ivx at a cell of primitive variables. The rows of the first
+    /// matrix are the left eigenvectors and the columns of the second the
+    /// right eigenvectors, so that both multiply to the identity.
+    ///
+    /// The default is the Euler system, whose eigenvectors in primitive
+    /// variables only depend on the density and the sound speed. Rows IDN
+    /// and ivx are the u - c and u + c waves, row IPR the entropy wave and
+    /// the remaining rows the advected components. Cells without a positive
+    /// density or sound speed are not decomposed.
+    fn eigenvectors(
+        &self,
+        w: &[Real],
+        comps: &[usize; NHYDRO],
+        ivx: usize,
+    ) -> (Matrix, Matrix) {
+        let mut left = identity();
+        let mut right = identity();
+
+        let rho = w[comps[IDN]];
+        let c = self.sound_speed(w, comps);
+        if !(rho > 0.0 && c > 0.0) {
+            return (left, right);
+        }
+
+        left[IDN][IDN] = 0.0;
+        left[IDN][ivx] = -0.5 * rho / c;
+        left[IDN][IPR] = 0.5 / (c * c);
+        left[ivx][ivx] = 0.5 * rho / c;
+        left[ivx][IPR] = 0.5 / (c * c);
+        left[IPR][IDN] = 1.0;
+        left[IPR][IPR] = -1.0 / (c * c);
+
+        right[ivx][IDN] = -c / rho;
+        right[IPR][IDN] = c * c;
+        right[IDN][ivx] = 1.0;
+        right[ivx][ivx] = c / rho;
+        right[IPR][ivx] = c * c;
+        right[IDN][IPR] = 1.0;
+        right[IPR][IPR] = 0.0;
+
+        (left, right)
+    }
+}
+
+/// Identity matrix over the components of a cell
+pub fn identity() -> Matrix {
+    std::array::from_fn(|m| std::array::from_fn(|n| (m == n) as i32 as Real))
+}
+
+/// Kinetic energy per unit volume of a cell of primitive variables
+pub fn kinetic_energy(w: &[Real], comps: &[usize; NHYDRO]) -> Real {
+    let [idn, iv1, iv2, iv3] = [IDN, IVX, IVY, IVZ].map(|n| comps[n]);
+    0.5 * w[idn] * (w[iv1] * w[iv1] + w[iv2] * w[iv2] + w[iv3] * w[iv3])
 }
@@ -0,0 +1,130 @@
+//! Ideal gas equation of state
+//!
+//! p = (gamma - 1) rho e, where e is the specific internal energy
+
+use crate::eos::eos_trait::{self, kinetic_energy};
+use crate::utils::{
+    common::set_comp,
+    defs::{Real, IDN, IPR, IVX, IVY, IVZ, NHYDRO},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct IdealGas {
+    // adiabatic index
+    pub gamma: Real,
+}
+
+impl Default for IdealGas {
+    fn default() -> Self {
+        Self { gamma: 1.4 }
+    }
+}
+
+impl eos_trait::EquationOfState for IdealGas {
+    unsafe fn primitive_to_conserved(
+        &self,
+        u: *mut Real,
+        w: &[Real],
+        comps: &[usize; NHYDRO],
+    ) {
+        let [idn, iv1, iv2, iv3, ipr] =
+            [IDN, IVX, IVY, IVZ, IPR].map(|n| comps[n]);
+        let rho = w[idn];
+
+        unsafe {
+            set_comp(u, idn, rho);
+            set_comp(u, iv1, rho * w[iv1]);
+            set_comp(u, iv2, rho * w[iv2]);
+            set_comp(u, iv3, rho * w[iv3]);
+            set_comp(u, ipr, self.energy(w, comps));
+        }
+    }
+
+    unsafe fn conserved_to_primitive(
+        &self,
+        w: *mut Real,
+        u: &[Real],
+        comps: &[usize; NHYDRO],
+    ) {
+        let [idn, iv1, iv2, iv3, ipr] =
+            [IDN, IVX, IVY, IVZ, IPR].map(|n| comps[n]);
+        let rho = u[idn];
+        let vx = u[iv1] / rho;
+        let vy = u[iv2] / rho;
+        let vz = u[iv3] / rho;
+        let ke = 0.5 * rho * (vx * vx + vy * vy + vz * vz);
+
+        unsafe {
+            set_comp(w, idn, rho);
+            set_comp(w, iv1, vx);
+            set_comp(w, iv2, vy);
+            set_comp(w, iv3, vz);
+            set_comp(w, ipr, (self.gamma - 1.0) * (u[ipr] - ke));
+        }
+    }
+
+    fn pressure(&self, w: &[Real], comps: &[usize; NHYDRO]) -> Real {
+        w[comps[IPR]]
+    }
+
+    fn sound_speed(&self, w: &[Real], comps: &[usize; NHYDRO]) -> Real {
+        (self.gamma * w[comps[IPR]] / w[comps[IDN]]).sqrt()
+    }
+
+    fn energy(&self, w: &[Real], comps: &[usize; NHYDRO]) -> Real {
+        kinetic_energy(w, comps) + w[comps[IPR]] / (self.gamma - 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eos::eos_trait::EquationOfState;
+    use crate::riemann::flux::LOCAL;
+    use crate::utils::common::dry_state;
+
+    #[test]
+    fn test_ideal_gas() {
+        let eos = IdealGas { gamma: 5.0 / 3.0 };
+        let comps = LOCAL;
+        let w = dry_state([2.0, 1.0, 0.0, 0.0, 3.0]);
+
+        approx::assert_abs_diff_eq!(
+            eos.sound_speed(&w, &comps),
+            2.5_f64.sqrt()
+        );
+        approx::assert_abs_diff_eq!(
+            eos.energy(&w, &comps),
+            1.0 + 4.5,
+            epsilon = 1e-14
+        );
+    }
+
+    #[test]
+    fn test_eigenvectors() {
+        let eos = IdealGas { gamma: 5.0 / 3.0 };
+        let comps = LOCAL;
+        let w = dry_state([2.0, 1.0, 0.0, 0.0, 3.0]);
+
+        let (left, right) = eos.eigenvectors(&w, &comps, IVX);
+        for (m, n) in itertools::iproduct!(0..NHYDRO, 0..NHYDRO) {
+            let lr: Real = (0..NHYDRO).map(|k| left[m][k] * right[k][n]).sum();
+            approx::assert_abs_diff_eq!(lr, (m == n) as i32 as Real);
+        }
+
+        // an entropy wave only changes the density
+        let dw = dry_state([1.0, 0.0, 0.0, 0.0, 0.0]);
+        for (k, l) in left.iter().enumerate() {
+            let dq: Real = l.iter().zip(dw).map(|(l, dw)| l * dw).sum();
+            approx::assert_abs_diff_eq!(dq, (k == IPR) as i32 as Real);
+        }
+
+        // a left-going sound wave dp = c^2 drho = -rho c du
+        let c = eos.sound_speed(&w, &comps);
+        let dw = dry_state([1.0, -c / 2.0, 0.0, 0.0, c * c]);
+        for (k, l) in left.iter().enumerate() {
+            let dq: Real = l.iter().zip(dw).map(|(l, dw)| l * dw).sum();
+            approx::assert_abs_diff_eq!(dq, (k == IDN) as i32 as Real);
+        }
+    }
+}
@@ -0,0 +1,301 @@
+//! Ideal gas equation of state of a mixture of dry air and vapors
+//!
+//! The primitive variables hold the total density and the vapor and cloud
+//! mass mixing ratios. The conserved variables hold the dry air density and
+//! the vapor and cloud densities. The pressure and internal energy are
+//! related by p = (gamma - 1) rho (e + latent) feps / fsig, where gamma is
+//! the adiabatic index of dry air and fsig, feps, latent are the mixture
+//! factors of Thermodynamics. The latent energy of the clouds is part of
+//! the total energy, so that phase changes conserve it.
+
+use crate::eos::eos_trait::{self, kinetic_energy};
+use crate::thermo::thermodynamics::Thermodynamics;
+use crate::utils::{
+    common::set_comp,
+    defs::{Real, IDN, IPR, IVX, IVY, IVZ, NHYDRO, NVAPOR},
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct IdealMoist {
+    // adiabatic index of dry air
+    pub gamma: Real,
+
+    pub thermo: Thermodynamics,
+}
+
+impl IdealMoist {
+    pub fn new(gamma: Real, thermo: Thermodynamics) -> Self {
+        assert_eq!(thermo.nvapor(), NVAPOR, "Invalid number of vapors");
+        Self { gamma, thermo }
+    }
+
+    /// Heat capacity over gas constant of the mixture, cv / R
+    pub fn kappa(&self, w: &[Real], comps: &[usize; NHYDRO]) -> Real {
+        self.thermo.fsig(w, comps) / self.thermo.feps(w, comps)
+            / (self.gamma - 1.0)
+    }
+
+    /// Condense supersaturated vapor into clouds and evaporate clouds into
+    /// subsaturated vapor in a cell of density rho, species mass mixing
+    /// ratios q and pressure pres. The density and the total energy of the
+    /// cell are unchanged. Returns the adjusted pressure.
+    pub fn adjust(
+        &self,
+        rho: Real,
+        q: &mut [Real],
+        pres: Real,
+    ) -> Real {
+        let thermo = &self.thermo;
+        let cv_dry = thermo.rd / (self.gamma - 1.0);
+
+        let mut rho_v: Vec<Real> = q.iter().map(|q| rho * q).collect();
+        let rho_d = rho - rho_v.iter().sum::<Real>();
+
+        let temp = thermo.temperature(pres, rho_d, &rho_v);
+        let temp =
+            thermo.saturation_adjustment(cv_dry, temp, rho_d, &mut rho_v);
+
+        for (q, rv) in q.iter_mut().zip(&rho_v) {
+            *q = rv / rho;
+        }
+        thermo.pressure(temp, rho_d, &rho_v)
+    }
+}
+
+impl Default for IdealMoist {
+    /// Dry air, the vapors have the properties of dry air and do not
+    /// condense
+    fn default() -> Self {
+        let ratio = [1.0; NVAPOR];
+        Self::new(1.4, Thermodynamics::new(&ratio, &ratio))
+    }
+}
+
+impl eos_trait::EquationOfState for IdealMoist {
+    unsafe fn primitive_to_conserved(
+        &self,
+        u: *mut Real,
+        w: &[Real],
+        comps: &[usize; NHYDRO],
+    ) {
+        let [idn, iv1, iv2, iv3, ipr] =
+            [IDN, IVX, IVY, IVZ, IPR].map(|n| comps[n]);
+        let rho = w[idn];
+        let qv = self.thermo.vapor_fraction(w, comps);
+
+        unsafe {
+            set_comp(u, idn, rho * (1.0 - qv));
+            for n in &comps[1..IVX] {
+                set_comp(u, *n, rho * w[*n]);
+            }
+            set_comp(u, iv1, rho * w[iv1]);
+            set_comp(u, iv2, rho * w[iv2]);
+            set_comp(u, iv3, rho * w[iv3]);
+            set_comp(u, ipr, self.energy(w, comps));
+        }
+    }
+
+    unsafe fn conserved_to_primitive(
+        &self,
+        w: *mut Real,
+        u: &[Real],
+        comps: &[usize; NHYDRO],
+    ) {
+        let [idn, iv1, iv2, iv3, ipr] =
+            [IDN, IVX, IVY, IVZ, IPR].map(|n| comps[n]);
+        let rho = u[idn] + self.thermo.vapor_fraction(u, comps);
+        let vx = u[iv1] / rho;
+        let vy = u[iv2] / rho;
+        let vz = u[iv3] / rho;
+        let ke = 0.5 * rho * (vx * vx + vy * vy + vz * vz);
+
+        // the mixture factors are linear in the vapor and cloud densities
+        let fsig = 1.0 + (self.thermo.fsig(u, comps) - 1.0) / rho;
+        let feps = 1.0 + (self.thermo.feps(u, comps) - 1.0) / rho;
+        let latent = self.thermo.latent(u, comps);
+
+        unsafe {
+            set_comp(w, idn, rho);
+            for n in &comps[1..IVX] {
+                set_comp(w, *n, u[*n] / rho);
+            }
+            set_comp(w, iv1, vx);
+            set_comp(w, iv2, vy);
+            set_comp(w, iv3, vz);
+            set_comp(
+                w,
+                ipr,
+                (self.gamma - 1.0) * (u[ipr] - ke + latent) * feps / fsig,
+            );
+        }
+    }
+
+    fn pressure(&self, w: &[Real], comps: &[usize; NHYDRO]) -> Real {
+        w[comps[IPR]]
+    }
+
+    fn sound_speed(&self, w: &[Real], comps: &[usize; NHYDRO]) -> Real {
+        let gamma = 1.0 + 1.0 / self.kappa(w, comps);
+        (gamma * w[comps[IPR]] / w[comps[IDN]]).sqrt()
+    }
+
+    fn energy(&self, w: &[Real], comps: &[usize; NHYDRO]) -> Real {
+        kinetic_energy(w, comps) + self.kappa(w, comps) * w[comps[IPR]]
+            - w[comps[IDN]] * self.thermo.latent(w, comps)
+    }
+
+    fn saturation_adjustment(
+        &self,
+        w: &mut [Real],
+        comps: &[usize; NHYDRO],
+    ) -> bool {
+        if self.thermo.cloud.iter().all(Option::is_none) {
+            return false;
+        }
+
+        let mut q = [0.0; NVAPOR];
+        for (n, q) in q.iter_mut().enumerate() {
+            *q = w[comps[1 + n]];
+        }
+        w[comps[IPR]] = self.adjust(w[comps[IDN]], &mut q, w[comps[IPR]]);
+        for (n, q) in q.iter().enumerate() {
+            w[comps[1 + n]] = *q;
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eos::{
+        eos::EquationOfState, eos_trait::EquationOfState as _,
+        ideal_gas::IdealGas,
+    };
+    #[cfg(feature = "moist")]
+    use crate::{riemann::flux, thermo::saturation::SaturationCurve};
+
+    /// Water vapor 1 condensing into cloud 2
+    #[cfg(feature = "moist")]
+    fn water() -> IdealMoist {
+        let mut thermo =
+            Thermodynamics::new(&[1.46, 1.46], &[0.622, Real::INFINITY]);
+        thermo.saturation(1, 2, SaturationCurve::water());
+        IdealMoist::new(1.4, thermo)
+    }
+
+    #[test]
+    fn test_dry_limit() {
+        let moist = IdealMoist::default();
+        let dry = IdealGas { gamma: 1.4 };
+        let mut comps = [0; NHYDRO];
+        for (n, comp) in comps.iter_mut().enumerate() {
+            *comp = n;
+        }
+
+        let mut w = [0.0; NHYDRO];
+        w[IDN] = 1.2;
+        w[IVX] = 0.3;
+        w[IPR] = 2.0;
+
+        let (mut u_moist, mut u_dry) = ([0.0; NHYDRO], [0.0; NHYDRO]);
+        let mut w1 = [0.0; NHYDRO];
+        unsafe {
+            moist.primitive_to_conserved(u_moist.as_mut_ptr(), &w, &comps);
+            dry.primitive_to_conserved(u_dry.as_mut_ptr(), &w, &comps);
+            moist.conserved_to_primitive(w1.as_mut_ptr(), &u_moist, &comps);
+        }
+
+        for n in 0..NHYDRO {
+            approx::assert_abs_diff_eq!(u_moist[n], u_dry[n]);
+            approx::assert_abs_diff_eq!(w1[n], w[n], epsilon = 1e-14);
+        }
+        approx::assert_abs_diff_eq!(
+            moist.sound_speed(&w, &comps),
+            dry.sound_speed(&w, &comps),
+            epsilon = 1e-14
+        );
+        approx::assert_abs_diff_eq!(
+            moist.kappa(&w, &comps),
+            2.5,
+            epsilon = 1e-14
+        );
+    }
+
+    #[cfg(feature = "moist")]
+    #[test]
+    fn test_moist_round_trip() {
+        let moist = water();
+        let mut w = [0.0; NHYDRO];
+        w[IDN] = 1.2;
+        w[1] = 0.01;
+        w[2] = 0.002;
+        w[IVX] = 0.3;
+        w[IVY] = -0.1;
+        w[IPR] = 1e5;
+
+        let u = flux::conserved(&w, &moist);
+        let mut w1 = [0.0; NHYDRO];
+        unsafe {
+            moist.conserved_to_primitive(w1.as_mut_ptr(), &u, &flux::LOCAL);
+        }
+
+        approx::assert_relative_eq!(u[IDN], 1.2 * 0.988);
+        approx::assert_relative_eq!(u[1], 1.2 * 0.01);
+        approx::assert_relative_eq!(u[2], 1.2 * 0.002);
+        for n in 0..NHYDRO {
+            approx::assert_relative_eq!(w1[n], w[n], max_relative = 1e-14);
+        }
+    }
+
+    #[cfg(feature = "moist")]
+    #[test]
+    fn test_saturation_adjustment_conserves() {
+        let moist = water();
+
+        // supersaturated at 300 K
+        let rho = 1.2;
+        let mut q = [0.03, 0.0];
+        let pres = moist.thermo.pressure(300.0, rho * 0.97, &[rho * 0.03, 0.0]);
+
+        // internal energy per unit volume, the total energy at rest
+        let energy = |q: &[Real], pres: Real| {
+            let (w, comps) = ([rho, q[0], q[1]], [0, 1, 2]);
+            let thermo = &moist.thermo;
+            thermo.fsig(&w, &comps) / thermo.feps(&w, &comps) / 0.4 * pres
+                - rho * thermo.latent(&w, &comps)
+        };
+        let energy0 = energy(&q, pres);
+
+        let pres1 = moist.adjust(rho, &mut q, pres);
+
+        let rho_d = rho * (1.0 - q[0] - q[1]);
+        let temp = moist.thermo.temperature(pres1, rho_d, &[rho * q[0], 0.0]);
+        assert!(q[1] > 0.0 && temp > 300.0);
+        approx::assert_abs_diff_eq!(q[0] + q[1], 0.03, epsilon = 1e-16);
+        approx::assert_abs_diff_eq!(rho_d, rho * 0.97, epsilon = 1e-15);
+        approx::assert_relative_eq!(
+            energy(&q, pres1),
+            energy0,
+            max_relative = 1e-14
+        );
+    }
+
+    #[test]
+    fn test_dry_saturation_adjustment() {
+        let mut eos = EquationOfState::new(2, 3, IdealMoist::default());
+        eos.w.data.fill(1.0);
+        let vapors = eos.w.icomp(1)..eos.w.icomp(IVX);
+        eos.w.data[vapors].fill(0.0);
+        eos.primitive_to_conserved();
+        let (w0, u0) = (eos.w.data.clone(), eos.u.data.clone());
+
+        eos.saturation_adjustment();
+
+        assert_eq!(eos.w.data, w0);
+        assert_eq!(eos.u.data, u0);
+        for u in eos.u.interior() {
+            approx::assert_abs_diff_eq!(u[eos.comps[IPR]], 1.5 + 2.5);
+        }
+    }
+}
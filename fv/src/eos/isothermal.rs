@@ -0,0 +1,97 @@
+//! Isothermal equation of state
+//!
+//! p = rho cs^2 with a constant sound speed cs. The energy equation is not
+//! needed: the pressure follows from the density and the energy component
+//! of the conserved variables holds the kinetic energy.
+
+use crate::eos::eos_trait::{self, kinetic_energy};
+use crate::utils::{
+    common::set_comp,
+    defs::{Real, IDN, IPR, IVX, IVY, IVZ, NHYDRO},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Isothermal {
+    // isothermal sound speed
+    pub cs: Real,
+}
+
+impl Default for Isothermal {
+    fn default() -> Self {
+        Self { cs: 1.0 }
+    }
+}
+
+impl eos_trait::EquationOfState for Isothermal {
+    unsafe fn primitive_to_conserved(
+        &self,
+        u: *mut Real,
+        w: &[Real],
+        comps: &[usize; NHYDRO],
+    ) {
+        let [idn, iv1, iv2, iv3, ipr] =
+            [IDN, IVX, IVY, IVZ, IPR].map(|n| comps[n]);
+        let rho = w[idn];
+
+        unsafe {
+            set_comp(u, idn, rho);
+            set_comp(u, iv1, rho * w[iv1]);
+            set_comp(u, iv2, rho * w[iv2]);
+            set_comp(u, iv3, rho * w[iv3]);
+            set_comp(u, ipr, self.energy(w, comps));
+        }
+    }
+
+    unsafe fn conserved_to_primitive(
+        &self,
+        w: *mut Real,
+        u: &[Real],
+        comps: &[usize; NHYDRO],
+    ) {
+        let [idn, iv1, iv2, iv3, ipr] =
+            [IDN, IVX, IVY, IVZ, IPR].map(|n| comps[n]);
+        let rho = u[idn];
+
+        unsafe {
+            set_comp(w, idn, rho);
+            set_comp(w, iv1, u[iv1] / rho);
+            set_comp(w, iv2, u[iv2] / rho);
+            set_comp(w, iv3, u[iv3] / rho);
+            set_comp(w, ipr, rho * self.cs * self.cs);
+        }
+    }
+
+    fn pressure(&self, w: &[Real], comps: &[usize; NHYDRO]) -> Real {
+        w[comps[IDN]] * self.cs * self.cs
+    }
+
+    fn sound_speed(&self, _w: &[Real], _comps: &[usize; NHYDRO]) -> Real {
+        self.cs
+    }
+
+    fn energy(&self, w: &[Real], comps: &[usize; NHYDRO]) -> Real {
+        kinetic_energy(w, comps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eos::eos_trait::EquationOfState;
+    use crate::riemann::flux::LOCAL;
+    use crate::utils::common::dry_state;
+
+    #[test]
+    fn test_isothermal() {
+        let eos = Isothermal { cs: 2.0 };
+        let comps = LOCAL;
+        let u = dry_state([3.0, 6.0, 0.0, -3.0, 0.0]);
+        let mut w = [0.0; NHYDRO];
+
+        unsafe { eos.conserved_to_primitive(w.as_mut_ptr(), &u, &comps) };
+
+        assert_eq!(w, dry_state([3.0, 2.0, 0.0, -1.0, 12.0]));
+        approx::assert_abs_diff_eq!(eos.pressure(&w, &comps), 12.0);
+        approx::assert_abs_diff_eq!(eos.sound_speed(&w, &comps), 2.0);
+    }
+}
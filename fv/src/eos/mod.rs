@@ -1 +1,8 @@
+#[allow(clippy::module_inception)]
 pub mod eos;
+pub mod eos_trait;
+pub mod ideal_gas;
+pub mod ideal_moist;
+pub mod isothermal;
+pub mod polytropic;
+pub mod shallow_water;
@@ -0,0 +1,100 @@
+//! Polytropic equation of state
+//!
+//! p = K rho^gamma, a barotropic gas of constant entropy. The energy
+//! component of the conserved variables holds the kinetic energy plus the
+//! internal energy p / (gamma - 1) of the polytrope.
+
+use crate::eos::eos_trait::{self, kinetic_energy};
+use crate::utils::{
+    common::set_comp,
+    defs::{Real, IDN, IPR, IVX, IVY, IVZ, NHYDRO},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Polytropic {
+    // polytropic constant
+    pub k: Real,
+
+    // polytropic index
+    pub gamma: Real,
+}
+
+impl Default for Polytropic {
+    fn default() -> Self {
+        Self { k: 1.0, gamma: 2.0 }
+    }
+}
+
+impl eos_trait::EquationOfState for Polytropic {
+    unsafe fn primitive_to_conserved(
+        &self,
+        u: *mut Real,
+        w: &[Real],
+        comps: &[usize; NHYDRO],
+    ) {
+        let [idn, iv1, iv2, iv3, ipr] =
+            [IDN, IVX, IVY, IVZ, IPR].map(|n| comps[n]);
+        let rho = w[idn];
+
+        unsafe {
+            set_comp(u, idn, rho);
+            set_comp(u, iv1, rho * w[iv1]);
+            set_comp(u, iv2, rho * w[iv2]);
+            set_comp(u, iv3, rho * w[iv3]);
+            set_comp(u, ipr, self.energy(w, comps));
+        }
+    }
+
+    unsafe fn conserved_to_primitive(
+        &self,
+        w: *mut Real,
+        u: &[Real],
+        comps: &[usize; NHYDRO],
+    ) {
+        let [idn, iv1, iv2, iv3, ipr] =
+            [IDN, IVX, IVY, IVZ, IPR].map(|n| comps[n]);
+        let rho = u[idn];
+
+        unsafe {
+            set_comp(w, idn, rho);
+            set_comp(w, iv1, u[iv1] / rho);
+            set_comp(w, iv2, u[iv2] / rho);
+            set_comp(w, iv3, u[iv3] / rho);
+            set_comp(w, ipr, self.k * rho.powf(self.gamma));
+        }
+    }
+
+    fn pressure(&self, w: &[Real], comps: &[usize; NHYDRO]) -> Real {
+        self.k * w[comps[IDN]].powf(self.gamma)
+    }
+
+    fn sound_speed(&self, w: &[Real], comps: &[usize; NHYDRO]) -> Real {
+        (self.gamma * self.pressure(w, comps) / w[comps[IDN]]).sqrt()
+    }
+
+    fn energy(&self, w: &[Real], comps: &[usize; NHYDRO]) -> Real {
+        kinetic_energy(w, comps) + self.pressure(w, comps) / (self.gamma - 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eos::eos_trait::EquationOfState;
+    use crate::riemann::flux::LOCAL;
+    use crate::utils::common::dry_state;
+
+    #[test]
+    fn test_polytropic() {
+        let eos = Polytropic { k: 0.5, gamma: 2.0 };
+        let comps = LOCAL;
+        let w = dry_state([2.0, 0.0, 0.0, 0.0, 0.0]);
+
+        approx::assert_abs_diff_eq!(eos.pressure(&w, &comps), 2.0);
+        approx::assert_abs_diff_eq!(
+            eos.sound_speed(&w, &comps),
+            2.0_f64.sqrt()
+        );
+        approx::assert_abs_diff_eq!(eos.energy(&w, &comps), 2.0);
+    }
+}
@@ -0,0 +1,179 @@
+//! Shallow-water equation of state
+//!
+//! The density component holds the fluid height h and the momentum
+//! components hold h v. The pressure is the depth-integrated hydrostatic
+//! pressure g h^2 / 2 and the gravity wave speed is sqrt(g h). The energy
+//! component of the conserved variables holds the kinetic plus potential
+//! energy h |v|^2 / 2 + g h^2 / 2.
+
+use crate::eos::eos_trait::{self, identity, kinetic_energy, Matrix};
+use crate::utils::{
+    common::set_comp,
+    defs::{Real, IDN, IPR, IVX, IVY, IVZ, NHYDRO},
+};
+
+/// Depth below which a cell is dry. The velocity of a dry cell is cut off
+/// to zero.
+pub const DRY_DEPTH: Real = 1e-8;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ShallowWater {
+    // gravitational acceleration
+    pub grav: Real,
+}
+
+impl Default for ShallowWater {
+    fn default() -> Self {
+        Self { grav: 1.0 }
+    }
+}
+
+impl eos_trait::EquationOfState for ShallowWater {
+    unsafe fn primitive_to_conserved(
+        &self,
+        u: *mut Real,
+        w: &[Real],
+        comps: &[usize; NHYDRO],
+    ) {
+        let [idn, iv1, iv2, iv3, ipr] =
+            [IDN, IVX, IVY, IVZ, IPR].map(|n| comps[n]);
+        let h = w[idn];
+
+        unsafe {
+            set_comp(u, idn, h);
+            set_comp(u, iv1, h * w[iv1]);
+            set_comp(u, iv2, h * w[iv2]);
+            set_comp(u, iv3, h * w[iv3]);
+            set_comp(u, ipr, self.energy(w, comps));
+        }
+    }
+
+    unsafe fn conserved_to_primitive(
+        &self,
+        w: *mut Real,
+        u: &[Real],
+        comps: &[usize; NHYDRO],
+    ) {
+        let [idn, iv1, iv2, iv3, ipr] =
+            [IDN, IVX, IVY, IVZ, IPR].map(|n| comps[n]);
+        let h = u[idn];
+        let hinv = if h > DRY_DEPTH { 1.0 / h } else { 0.0 };
+
+        unsafe {
+            set_comp(w, idn, h);
+            set_comp(w, iv1, u[iv1] * hinv);
+            set_comp(w, iv2, u[iv2] * hinv);
+            set_comp(w, iv3, u[iv3] * hinv);
+            set_comp(w, ipr, 0.5 * self.grav * h * h);
+        }
+    }
+
+    fn pressure(&self, w: &[Real], comps: &[usize; NHYDRO]) -> Real {
+        let h = w[comps[IDN]];
+        0.5 * self.grav * h * h
+    }
+
+    fn sound_speed(&self, w: &[Real], comps: &[usize; NHYDRO]) -> Real {
+        (self.grav * w[comps[IDN]].max(0.0)).sqrt()
+    }
+
+    fn energy(&self, w: &[Real], comps: &[usize; NHYDRO]) -> Real {
+        kinetic_energy(w, comps) + self.pressure(w, comps)
+    }
+
+    /// Rows IDN and ivx are the u - c and u + c gravity waves of the
+    /// height and normal velocity, the remaining components are advected.
+    /// Dry cells are not decomposed.
+    fn eigenvectors(
+        &self,
+        w: &[Real],
+        comps: &[usize; NHYDRO],
+        ivx: usize,
+    ) -> (Matrix, Matrix) {
+        let mut left = identity();
+        let mut right = identity();
+
+        let h = w[comps[IDN]];
+        if h <= DRY_DEPTH {
+            return (left, right);
+        }
+        let c = self.sound_speed(w, comps);
+
+        left[IDN][IDN] = 0.5;
+        left[IDN][ivx] = -0.5 * h / c;
+        left[ivx][IDN] = 0.5;
+        left[ivx][ivx] = 0.5 * h / c;
+
+        right[ivx][IDN] = -c / h;
+        right[IDN][ivx] = 1.0;
+        right[ivx][ivx] = c / h;
+
+        (left, right)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eos::eos_trait::EquationOfState;
+    use crate::riemann::flux::LOCAL;
+    use crate::utils::common::dry_state;
+
+    #[test]
+    fn test_shallow_water() {
+        let eos = ShallowWater { grav: 9.8 };
+        let comps = LOCAL;
+        let w = dry_state([2.0, 0.5, -1.0, 0.0, 0.0]);
+        let mut u = [0.0; NHYDRO];
+        let mut w1 = [0.0; NHYDRO];
+
+        unsafe {
+            eos.primitive_to_conserved(u.as_mut_ptr(), &w, &comps);
+            eos.conserved_to_primitive(w1.as_mut_ptr(), &u, &comps);
+        }
+
+        assert_eq!([u[IDN], u[IVX], u[IVY]], [2.0, 1.0, -2.0]);
+        approx::assert_abs_diff_eq!(u[IPR], 1.25 + 19.6, epsilon = 1e-12);
+        approx::assert_abs_diff_eq!(w1[IPR], 19.6, epsilon = 1e-12);
+        approx::assert_abs_diff_eq!(
+            eos.sound_speed(&w, &comps),
+            19.6_f64.sqrt()
+        );
+    }
+
+    #[test]
+    fn test_dry_cell() {
+        let eos = ShallowWater { grav: 9.8 };
+        let comps = LOCAL;
+        let u = dry_state([0.5 * DRY_DEPTH, 1e-12, -1e-12, 0.0, 0.0]);
+        let mut w = [0.0; NHYDRO];
+
+        unsafe { eos.conserved_to_primitive(w.as_mut_ptr(), &u, &comps) };
+
+        assert_eq!(w, dry_state([0.5 * DRY_DEPTH, 0.0, 0.0, 0.0, w[IPR]]));
+
+        let w = dry_state([-1e-12, 0.0, 0.0, 0.0, 0.0]);
+        assert_eq!(eos.sound_speed(&w, &comps), 0.0);
+    }
+
+    #[test]
+    fn test_eigenvectors() {
+        let eos = ShallowWater { grav: 9.8 };
+        let comps = LOCAL;
+        let w = dry_state([2.0, 0.5, -1.0, 0.0, 19.6]);
+
+        let (left, right) = eos.eigenvectors(&w, &comps, IVY);
+        for (m, n) in itertools::iproduct!(0..NHYDRO, 0..NHYDRO) {
+            let lr: Real = (0..NHYDRO).map(|k| left[m][k] * right[k][n]).sum();
+            approx::assert_abs_diff_eq!(lr, (m == n) as i32 as Real);
+        }
+
+        // a right-going gravity wave dh = h du / c only has a u + c part
+        let c = eos.sound_speed(&w, &comps);
+        let dw = dry_state([1.0, 0.0, c / 2.0, 0.0, 0.0]);
+        for (k, l) in left.iter().enumerate() {
+            let dq: Real = l.iter().zip(dw).map(|(l, dw)| l * dw).sum();
+            approx::assert_abs_diff_eq!(dq, (k == IVY) as i32 as Real);
+        }
+    }
+}
@@ -1,17 +1,28 @@
 //! Hydrodynamics module
 //!
 //! Hydrodynamics provides the following functions:
-//! 1. Reconstruct the left and right states (hydro_reconstruct.rs)
+//! 1. Reconstruct the left and right states (hydro_reconstruct.rs),
+//!    optionally in characteristic variables
 //! 2. Solve the Riemann problem (hydro_riemann.rs)
 //! 3. Add flux divergence to the conserved variables (hydro_flux_divergence.rs)
+//! 4. Balance bottom topography of shallow water (hydro_topography.rs)
 //!
 //! Hydrodynamics will modify the conserved variables in the EquationOfState
 
-use itertools::izip;
+use itertools::{iproduct, izip};
 use crate::block::block2d::Block2D;
-use crate::eos::eos::EquationOfState;
-use crate::riemann::roe_shallow_water::roe_shallow_water;
-use crate::utils::defs::{Real, DIMENSION, NGHOST, NHYDRO};
+use crate::coord::coordinates::{CoordinateSystem, Coordinates};
+use crate::coord::gnomonic;
+use crate::eos::{eos::EquationOfState, eos_trait};
+use crate::reconstruct::reconstruction::Reconstruction;
+use crate::riemann::{
+    flux::{gather, LOCAL},
+    riemann_solver::RiemannSolver,
+};
+use crate::utils::{
+    common::{add_comp, set_comp},
+    defs::{Real, DIMENSION, IVX, IVY, NGHOST, NHYDRO},
+};
 
 pub struct Hydro {
     // components
@@ -23,6 +34,23 @@ pub struct Hydro {
 
     // fluxes
     pub flx: [Block2D<Real>; DIMENSION],
+
+    // pressure removed from the left and right states by the hydrostatic
+    // reconstruction over bottom topography
+    pub bed: [Block2D<Real>; DIMENSION],
+
+    // scratch block of the reconstructed free surface over bottom
+    // topography, allocated on first use
+    pub eta: Option<Block2D<Real>>,
+
+    // interface states of both directional sweeps
+    pub recon: Reconstruction,
+
+    // reconstruct in the characteristic variables of the equation of state
+    pub characteristic: bool,
+
+    // interface flux of both directional sweeps
+    pub riemann: RiemannSolver,
 }
 
 impl Hydro {
@@ -46,6 +74,11 @@ impl Hydro {
             Block2D::new(NHYDRO, dim2, dim1, NGHOST),
         ];
 
+        let bed = [
+            Block2D::new(2, dim2, dim1, NGHOST),
+            Block2D::new(2, dim2, dim1, NGHOST),
+        ];
+
         // let comps: Vec<_> = (0..NHYDRO).map(|x| wls[0].icomp(x)).collect();
         let mut comps = [0; NHYDRO];
         for (i, comp) in comps.iter_mut().enumerate().take(NHYDRO) {
@@ -57,6 +90,11 @@ impl Hydro {
             wls,
             wrs,
             flx,
+            bed,
+            eta: None,
+            recon: Reconstruction::default(),
+            characteristic: false,
+            riemann: RiemannSolver::default(),
         }
     }
 
@@ -67,69 +105,275 @@ impl Hydro {
     ///           ^ ^
     ///           | |
     ///       wl(i) wr(i)
-    pub unsafe fn riemann_solver_x1(&mut self) {
+    ///
+    /// On a cubed-sphere panel the velocities are projected onto the face
+    /// normal, see face_normal_flux.
+    pub unsafe fn riemann_solver_x1<E: eos_trait::EquationOfState>(
+        &mut self,
+        eos: &EquationOfState<E>,
+        coord: &Coordinates,
+    ) {
         let wl = self.wls[Hydro::X1DIR].interior_f1();
         let wr = self.wrs[Hydro::X1DIR].interior_f1();
         let flx = self.flx[Hydro::X1DIR].interior_f1_mut();
         let pos = [0];
 
-        for (flx, wl, wr) in izip!(flx, wl, wr) {
+        let x2v = &coord.x2v[NGHOST..coord.x2v.len() - NGHOST];
+        let x1f = &coord.x1f[NGHOST..coord.x1f.len() - NGHOST];
+        let xf = iproduct!(x2v, x1f);
+
+        for (flx, wl, wr, (x2, x1)) in izip!(flx, wl, wr, xf) {
             unsafe {
-                roe_shallow_water(flx, wl, wr, Hydro::X1DIR, &self.comps, &pos);
+                if let CoordinateSystem::CubedSphere { .. } = coord.system {
+                    face_normal_flux(
+                        &self.riemann,
+                        flx,
+                        wl,
+                        wr,
+                        Hydro::X1DIR,
+                        &self.comps,
+                        &eos.law,
+                        gnomonic::cos_angle(*x1, *x2),
+                    );
+                } else {
+                    self.riemann.solve(
+                        flx,
+                        wl,
+                        wr,
+                        Hydro::X1DIR,
+                        &self.comps,
+                        &pos,
+                        &eos.law,
+                    );
+                }
             }
         }
     }
 
+    /// # Safety
+    ///
     /// Riemann solver for the x2 direction
+    ///
+    /// ```text
     /// ------
     /// w_{-1}
-    ///   * <----- wl(i)
+    ///   * <----- wl(j)
     /// ------
-    ///   * <----- wr(i)
+    ///   * <----- wr(j)
     /// w_{0}
     /// ------
-    pub fn riemann_solver_x2(&mut self) {
-        unimplemented!();
-        // WIP pseudocode
-        // let wl = wls[X2DIR].interior_f2();
-        // let wr = wrs[X2DIR].interior_f2();
-        // let flx = flx[X2DIR].interior_f2_mut();
-        //
-        // for (flx, wl, wr) in izip!(flx, wl, wr)
-        // {
-        // roe_shallow_water(flx, wl, wr, X2DIR);
-        // }
+    /// ```
+    ///
+    /// On a cubed-sphere panel the velocities are projected onto the face
+    /// normal, see face_normal_flux.
+    pub unsafe fn riemann_solver_x2<E: eos_trait::EquationOfState>(
+        &mut self,
+        eos: &EquationOfState<E>,
+        coord: &Coordinates,
+    ) {
+        let wl = self.wls[Hydro::X2DIR].interior_f2();
+        let wr = self.wrs[Hydro::X2DIR].interior_f2();
+        let flx = self.flx[Hydro::X2DIR].interior_f2_mut();
+        let pos = [0];
+
+        let x2f = &coord.x2f[NGHOST..coord.x2f.len() - NGHOST];
+        let x1v = &coord.x1v[NGHOST..coord.x1v.len() - NGHOST];
+        let xf = iproduct!(x2f, x1v);
+
+        for (flx, wl, wr, (x2, x1)) in izip!(flx, wl, wr, xf) {
+            unsafe {
+                if let CoordinateSystem::CubedSphere { .. } = coord.system {
+                    face_normal_flux(
+                        &self.riemann,
+                        flx,
+                        wl,
+                        wr,
+                        Hydro::X2DIR,
+                        &self.comps,
+                        &eos.law,
+                        gnomonic::cos_angle(*x1, *x2),
+                    );
+                } else {
+                    self.riemann.solve(
+                        flx,
+                        wl,
+                        wr,
+                        Hydro::X2DIR,
+                        &self.comps,
+                        &pos,
+                        &eos.law,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Maximum stable time step of the interior cells
+    ///
+    /// The signal speed |v| + c in each direction is compared with the cell
+    /// width in that direction, taken as the cell volume over the larger of
+    /// its two face areas so that a face on a polar axis, whose area
+    /// vanishes, does not widen the cell.
+    pub fn new_dt<E: eos_trait::EquationOfState>(
+        &self,
+        eos: &EquationOfState<E>,
+        coord: &Coordinates,
+        cfl: Real,
+    ) -> Real {
+        let iv1 = self.comps[IVX];
+        let iv2 = self.comps[IVY];
+
+        let w = eos.w.interior();
+        let area1 = coord.area1.interior();
+        let area1_p1 = coord.area1.interior_shift(0, 1);
+        let area2 = coord.area2.interior();
+        let area2_p1 = coord.area2.interior_shift(1, 0);
+        let vol = coord.vol.interior();
+
+        let mut min_dt = Real::MAX;
+        for (w, area1, area1_p1, area2, area2_p1, vol) in
+            izip!(w, area1, area1_p1, area2, area2_p1, vol)
+        {
+            let cs = eos.sound_speed(w);
+            let dx1 = vol[0] / area1[0].max(area1_p1[0]);
+            let dx2 = vol[0] / area2[0].max(area2_p1[0]);
+            let dt1 = dx1 / (w[iv1].abs() + cs);
+            let dt2 = dx2 / (w[iv2].abs() + cs);
+            min_dt = min_dt.min(dt1).min(dt2);
+        }
+
+        cfl * min_dt
     }
 
     /// Add flux divergence to the conserved variables
+    ///
+    /// ```text
     /// |  u_{0}  |
     /// ^         ^
     /// |         |
     /// flx(i)    flx(i+1)
-    pub fn add_flux_divergence(&self, _eos: &mut EquationOfState, _dt: Real) {
-        unimplemented!();
-        // WIP pseudocode
-        // let mut u = eos.u.interior_mut();
-        // let flx = flx[ivx].interior();
-        // let flx_p1 = flx[ivx].interior().offset_x1(1);
-        // let area = coord.area1(0);
-        // let area_p1 = coord.area(1);
-        // let vol = coord.vol();
-        //
-        // for (mut u, flx, flx_p1, area, area_p1, vol) in
-        // izip!(w, flx, flx_p1, area, area_p1, vol)
-        // {
-        // for n in ns..ne {
-        // w[n] -= dt * (flx_p1[n]*area_p1 - flx[n]*area) / vol;
-        // }
-        // }
+    /// ```
+    pub fn add_flux_divergence<E: eos_trait::EquationOfState>(
+        &self,
+        eos: &mut EquationOfState<E>,
+        coord: &Coordinates,
+        dt: Real,
+    ) {
+        // x1 direction
+        let u = eos.u.interior_mut();
+        let flx = self.flx[Hydro::X1DIR].interior();
+        let flx_p1 = self.flx[Hydro::X1DIR].interior_shift(0, 1);
+        let area = coord.area1.interior();
+        let area_p1 = coord.area1.interior_shift(0, 1);
+        let vol = coord.vol.interior();
+
+        for (u, flx, flx_p1, area, area_p1, vol) in
+            izip!(u, flx, flx_p1, area, area_p1, vol)
+        {
+            for n in self.comps {
+                unsafe {
+                    add_comp(
+                        u,
+                        n,
+                        -dt * (flx_p1[n] * area_p1[0] - flx[n] * area[0])
+                            / vol[0],
+                    );
+                }
+            }
+        }
+
+        // x2 direction
+        let u = eos.u.interior_mut();
+        let flx = self.flx[Hydro::X2DIR].interior();
+        let flx_p1 = self.flx[Hydro::X2DIR].interior_shift(1, 0);
+        let area = coord.area2.interior();
+        let area_p1 = coord.area2.interior_shift(1, 0);
+        let vol = coord.vol.interior();
+
+        for (u, flx, flx_p1, area, area_p1, vol) in
+            izip!(u, flx, flx_p1, area, area_p1, vol)
+        {
+            for n in self.comps {
+                unsafe {
+                    add_comp(
+                        u,
+                        n,
+                        -dt * (flx_p1[n] * area_p1[0] - flx[n] * area[0])
+                            / vol[0],
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// # Safety
+///
+/// Riemann solver at a face of a cubed-sphere panel, whose unit basis
+/// vectors e1, e2 meet at an angle of cosine g12. The states are rotated
+/// into the orthonormal frame of the face normal and tangent, where the
+/// normal velocity of a v1, v2 state across an x1-face is v1 sin, and the
+/// normal and tangential momentum fluxes are rotated back onto e1, e2.
+#[allow(clippy::too_many_arguments)]
+unsafe fn face_normal_flux<E: eos_trait::EquationOfState>(
+    riemann: &RiemannSolver,
+    flx: *mut Real,
+    wl: &[Real],
+    wr: &[Real],
+    dir: usize,
+    comps: &[usize; NHYDRO],
+    eos: &E,
+    g12: Real,
+) {
+    let (ivn, ivt) = match dir {
+        Hydro::X1DIR => (IVX, IVY),
+        Hydro::X2DIR => (IVY, IVX),
+        _ => panic!("Invalid direction"),
+    };
+    let sin = (1.0 - g12 * g12).sqrt();
+
+    let to_face = |w: &[Real]| {
+        let mut w = gather(w, comps);
+        let (vn, vt) = (w[ivn] * sin, w[ivt] + g12 * w[ivn]);
+        w[ivn] = vn;
+        w[ivt] = vt;
+        w
+    };
+    let (wl, wr) = (to_face(wl), to_face(wr));
+
+    let mut f = [0.0; NHYDRO];
+    unsafe {
+        riemann.solve(f.as_mut_ptr(), &wl, &wr, dir, &LOCAL, &[0], eos);
+    }
+    let (fn_, ft) = (f[ivn], f[ivt]);
+    f[ivn] = fn_ / sin;
+    f[ivt] = ft - g12 * fn_ / sin;
+
+    for (n, f) in f.iter().enumerate() {
+        unsafe { set_comp(flx, comps[n], *f) };
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::utils::defs::{NGHOST, NHYDRO};
+    use crate::coord::coordinates::CoordinateSystem;
+    use crate::eos::{ideal_gas::IdealGas, shallow_water::ShallowWater};
+    use crate::utils::defs::{IDN, NGHOST, NHYDRO};
+
+    /// Fill a square block with a shallow-water state that is symmetric
+    /// under the exchange of x1 and x2 (h(j, i) = h(i, j), u(j, i) = v(i, j))
+    fn fill_symmetric(eos: &mut EquationOfState<ShallowWater>, dim: usize) {
+        let ng = NGHOST as i32;
+        for j in -ng..dim as i32 + ng {
+            for i in -ng..dim as i32 + ng {
+                let (x, y) = (i as Real, j as Real);
+                *eos.w.set(0, j, i) = 1.0 + 0.1 * (x + y) + 0.01 * x * y;
+                *eos.w.set(IVX, j, i) = 0.1 * x + 0.02 * y - 0.05;
+                *eos.w.set(IVY, j, i) = 0.1 * y + 0.02 * x - 0.05;
+            }
+        }
+    }
 
     #[test]
     fn hydro_new() {
@@ -151,4 +395,164 @@ mod tests {
             assert_eq!(wr.nghost, NGHOST);
         }
     }
+
+    #[test]
+    fn riemann_solver_x2_symmetric() {
+        let dim = 6;
+        let mut hydro = Hydro::new(dim, dim);
+        let mut eos = EquationOfState::new(dim, dim, ShallowWater::default());
+        let coord = Coordinates::new(
+            CoordinateSystem::Cartesian,
+            dim,
+            dim,
+            (0.0, 1.0),
+            (0.0, 1.0),
+        );
+        fill_symmetric(&mut eos, dim);
+
+        hydro.recon = Reconstruction::Weno3;
+        hydro.reconstruct_x1(&eos);
+        hydro.reconstruct_x2(&eos);
+
+        for riemann in [
+            RiemannSolver::RoeShallowWater,
+            RiemannSolver::Lmars,
+            RiemannSolver::Hllc,
+            RiemannSolver::Hlle,
+            RiemannSolver::Rusanov,
+        ] {
+            hydro.riemann = riemann;
+            unsafe {
+                hydro.riemann_solver_x1(&eos, &coord);
+                hydro.riemann_solver_x2(&eos, &coord);
+            }
+
+            // rotating the problem swaps the two velocity components
+            let rotated = [0, 2, 1];
+
+            for (n, m) in rotated.iter().enumerate() {
+                for j in 0..dim as i32 {
+                    for i in 0..=dim as i32 {
+                        let f1 = hydro.flx[Hydro::X1DIR].get(n, j, i);
+                        let f2 = hydro.flx[Hydro::X2DIR].get(*m, i, j);
+
+                        assert!(f1.is_finite());
+                        approx::assert_abs_diff_eq!(f1, f2, epsilon = 1e-12);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn add_flux_divergence_conserves_mass() {
+        let dim = 6;
+        let dt = 0.01;
+        let mut hydro = Hydro::new(dim, dim);
+        let mut eos =
+            EquationOfState::new(dim, dim, ShallowWater { grav: 2.0 });
+        let coord = Coordinates::new(
+            CoordinateSystem::Cartesian,
+            dim,
+            dim,
+            (0.0, 2.0),
+            (0.0, 1.0),
+        );
+        fill_symmetric(&mut eos, dim);
+        eos.primitive_to_conserved();
+
+        let idn = hydro.comps[IDN];
+        let total_mass = |eos: &EquationOfState<ShallowWater>| -> Real {
+            izip!(eos.u.interior(), coord.vol.interior())
+                .map(|(u, vol)| u[idn] * vol[0])
+                .sum()
+        };
+
+        let mass0 = total_mass(&eos);
+
+        hydro.recon = Reconstruction::Weno3;
+        hydro.reconstruct_x1(&eos);
+        hydro.reconstruct_x2(&eos);
+        unsafe {
+            hydro.riemann_solver_x1(&eos, &coord);
+            hydro.riemann_solver_x2(&eos, &coord);
+        }
+        hydro.add_flux_divergence(&mut eos, &coord, dt);
+
+        // net mass flowing in through the domain boundaries
+        let n = dim as i32;
+        let mut inflow = 0.0;
+        let flx1 = &hydro.flx[Hydro::X1DIR];
+        let flx2 = &hydro.flx[Hydro::X2DIR];
+        let (area1, area2) = (&coord.area1, &coord.area2);
+        for k in 0..n {
+            inflow += flx1.get(0, k, 0) * area1.get(0, k, 0)
+                - flx1.get(0, k, n) * area1.get(0, k, n)
+                + flx2.get(0, 0, k) * area2.get(0, 0, k)
+                - flx2.get(0, n, k) * area2.get(0, n, k);
+        }
+
+        approx::assert_abs_diff_ne!(inflow, 0.0, epsilon = 1e-6);
+        approx::assert_abs_diff_eq!(
+            total_mass(&eos),
+            mass0 + dt * inflow,
+            epsilon = 1e-12
+        );
+    }
+
+    #[test]
+    fn new_dt() {
+        let (dim2, dim1) = (4, 5);
+        let hydro = Hydro::new(dim2, dim1);
+        let mut eos = EquationOfState::new(dim2, dim1, IdealGas::default());
+        let coord = Coordinates::new(
+            CoordinateSystem::Cartesian,
+            dim2,
+            dim1,
+            (0.0, 2.0),
+            (0.0, 1.0),
+        );
+
+        eos.w.data.fill(1.0);
+        *eos.w.set(IVY, 1, 2) = -3.0;
+
+        // dx1 = 0.2, dx2 = 0.5, the fastest signal is |v2| + c in x2
+        let cs = 1.4_f64.sqrt();
+        let expected = 0.8 * (0.2 / (1.0 + cs)).min(0.5 / (3.0 + cs));
+
+        approx::assert_abs_diff_eq!(
+            hydro.new_dt(&eos, &coord, 0.8),
+            expected,
+            epsilon = 1e-14
+        );
+    }
+
+    /// The cell on the axis of a cylindrical grid limits the time step in
+    /// the radial direction
+    #[test]
+    fn new_dt_axis() {
+        let (dim2, dim1) = (1, 4);
+        let hydro = Hydro::new(dim2, dim1);
+        let mut eos = EquationOfState::new(dim2, dim1, IdealGas::default());
+        let coord = Coordinates::new(
+            CoordinateSystem::Cylindrical,
+            dim2,
+            dim1,
+            (0.0, 2.0 * std::f64::consts::PI),
+            (0.0, 1.0),
+        );
+
+        eos.w.data.fill(1.0);
+        *eos.w.set(IVX, 0, 0) = 10.0;
+
+        // radial width of the axis cell is vol / area = dr / 2
+        let cs = 1.4_f64.sqrt();
+        let expected = 0.8 * 0.125 / (10.0 + cs);
+
+        approx::assert_abs_diff_eq!(
+            hydro.new_dt(&eos, &coord, 0.8),
+            expected,
+            epsilon = 1e-14
+        );
+    }
 }
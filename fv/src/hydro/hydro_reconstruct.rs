@@ -1,16 +1,19 @@
 //! Implements the hydrodynamics module of the code.
 //!
 //! This code provides the reconstruction of the left and right states
-//! from the cell-centered primitive variables.
+//! from the cell-centered primitive variables. In characteristic mode the
+//! stencil of each cell is projected onto the eigenvectors of the equation
+//! of state at its center.
 
 use itertools::izip;
-use crate::eos::eos::EquationOfState;
-use crate::reconstruct::{weno3::interp_weno3, weno5::interp_weno5};
+use crate::block::block2d::Block2D;
+use crate::eos::{eos::EquationOfState, eos_trait};
 use crate::utils::{
     common::set_comp,
+    defs::{Real, IVX, IVY},
 };
 
-use crate::hydro::hydro::Hydro;
+use crate::hydro::{hydro::Hydro, hydro_topography::free_surface};
 
 impl Hydro {
     /// Reconstruct the left and right states in the x1 direction
@@ -18,61 +21,67 @@ impl Hydro {
     ///                    ^       ^
     ///                    |       |
     ///                    wr(i)   wl(i+1)
-    pub fn reconstruct_x1(&mut self, eos: &EquationOfState, order: usize) {
-        let wl = self.wls[Self::X1DIR].interior_x1_mut(1);
-        let wr = self.wrs[Self::X1DIR].interior_x1_mut(0);
-
-        let wm2 = eos.w.interior_x1(-2);
-        let wm1 = eos.w.interior_x1(-1);
-        let w = eos.w.interior_x1(0);
-        let wp1 = eos.w.interior_x1(1);
-        let wp2 = eos.w.interior_x1(2);
-
-        match order {
-            1 => {
-                for (wl, wr, w) in izip!(wl, wr, w) {
-                    for n in self.comps {
-                        unsafe {
-                            set_comp(wl, n, w[n]);
-                            set_comp(wr, n, w[n]);
-                        }
-                    }
-                }
+    ///
+    /// Over bottom topography the free surface is reconstructed and the
+    /// heights are cut by the hydrostatic reconstruction.
+    pub fn reconstruct_x1<E: eos_trait::EquationOfState>(
+        &mut self,
+        eos: &EquationOfState<E>,
+    ) {
+        match &eos.zb {
+            Some(zb) => {
+                let mut eta = self.eta.take().unwrap_or_else(|| eos.w.clone());
+                free_surface(&mut eta, &eos.w, zb);
+                self.interpolate_x1(&eta, eos);
+                self.eta = Some(eta);
+                self.hydrostatic_reconstruction(eos, zb, Self::X1DIR);
             },
+            None => self.interpolate_x1(&eos.w, eos),
+        }
+    }
 
-            3 => {
-                for (wl, wr, wm1, w, wp1) in izip!(wl, wr, wm1, w, wp1) {
-                    for n in self.comps {
-                        unsafe {
-                            set_comp(wl, n, interp_weno3(wp1[n], w[n], wm1[n]));
-                            set_comp(wr, n, interp_weno3(wm1[n], w[n], wp1[n]));
-                        }
-                    }
-                }
-            },
+    fn interpolate_x1<E: eos_trait::EquationOfState>(
+        &mut self,
+        w: &Block2D<Real>,
+        eos: &EquationOfState<E>,
+    ) {
+        let wl = self.wls[Self::X1DIR].interior_x1_mut(1);
+        let wr = self.wrs[Self::X1DIR].interior_x1_mut(0);
 
-            5 => {
-                for (wl, wr, wm2, wm1, w, wp1, wp2) in
-                    izip!(wl, wr, wm2, wm1, w, wp1, wp2)
-                {
-                    for n in self.comps {
-                        unsafe {
-                            set_comp(
-                                wl,
-                                n,
-                                interp_weno5(wp2[n], wp1[n], w[n], wm1[n], wm2[n]),
-                            );
-                            set_comp(
-                                wr,
-                                n,
-                                interp_weno5(wm2[n], wm1[n], w[n], wp1[n], wp2[n]),
-                            );
-                        }
-                    }
+        let wm2 = w.interior_x1(-2);
+        let wm1 = w.interior_x1(-1);
+        let wp1 = w.interior_x1(1);
+        let wp2 = w.interior_x1(2);
+        let w = w.interior_x1(0);
+        // cells of the primitive variables, w may hold the free surface
+        let wcm2 = eos.w.interior_x1(-2);
+        let wcm1 = eos.w.interior_x1(-1);
+        let wc = eos.w.interior_x1(0);
+        let wcp1 = eos.w.interior_x1(1);
+        let wcp2 = eos.w.interior_x1(2);
+
+        for (wl, wr, wm2, wm1, w, wp1, wp2, wcm2, wcm1, wc, wcp1, wcp2) in
+            izip!(wl, wr, wm2, wm1, w, wp1, wp2, wcm2, wcm1, wc, wcp1, wcp2)
+        {
+            let stencil = self
+                .comps
+                .map(|n| [wm2[n], wm1[n], w[n], wp1[n], wp2[n]]);
+            let faces = if self.characteristic {
+                let (left, right) = eos.law.eigenvectors(wc, &self.comps, IVX);
+                self.recon.characteristic_faces(&stencil, &left, &right)
+            } else {
+                let pres = || {
+                    [wcm2, wcm1, wc, wcp1, wcp2]
+                        .map(|w| eos.law.pressure(w, &self.comps))
+                };
+                self.recon.cell_faces(&stencil, IVX, pres)
+            };
+            for (&n, (left, right)) in self.comps.iter().zip(faces) {
+                unsafe {
+                    set_comp(wr, n, left);
+                    set_comp(wl, n, right);
                 }
-            },
-
-            _ => panic!("Invalid order"),
+            }
         }
     }
 
@@ -87,71 +96,83 @@ impl Hydro {
     /// w_{1}
     /// ------
     /// w_{2}
-    pub fn reconstruct_x2(&mut self, eos: &EquationOfState, order: usize) {
-        let wl = self.wls[Self::X2DIR].interior_x2_mut(1);
-        let wr = self.wrs[Self::X2DIR].interior_x2_mut(0);
-
-        let wm2 = eos.w.interior_x2(-2);
-        let wm1 = eos.w.interior_x2(-1);
-        let w = eos.w.interior_x2(0);
-        let wp1 = eos.w.interior_x2(1);
-        let wp2 = eos.w.interior_x2(2);
-
-        match order {
-            1 => {
-                for (wl, wr, w) in izip!(wl, wr, w) {
-                    for n in self.comps {
-                        unsafe {
-                            set_comp(wl, n, w[n]);
-                            set_comp(wr, n, w[n]);
-                        }
-                    }
-                }
+    ///
+    /// Over bottom topography the free surface is reconstructed and the
+    /// heights are cut by the hydrostatic reconstruction.
+    pub fn reconstruct_x2<E: eos_trait::EquationOfState>(
+        &mut self,
+        eos: &EquationOfState<E>,
+    ) {
+        match &eos.zb {
+            Some(zb) => {
+                let mut eta = self.eta.take().unwrap_or_else(|| eos.w.clone());
+                free_surface(&mut eta, &eos.w, zb);
+                self.interpolate_x2(&eta, eos);
+                self.eta = Some(eta);
+                self.hydrostatic_reconstruction(eos, zb, Self::X2DIR);
             },
+            None => self.interpolate_x2(&eos.w, eos),
+        }
+    }
 
-            3 => {
-                for (wl, wr, wm1, w, wp1) in izip!(wl, wr, wm1, w, wp1) {
-                    for n in self.comps {
-                        unsafe {
-                            set_comp(wl, n, interp_weno3(wp1[n], w[n], wm1[n]));
-                            set_comp(wr, n, interp_weno3(wm1[n], w[n], wp1[n]));
-                        }
-                    }
-                }
-            },
+    fn interpolate_x2<E: eos_trait::EquationOfState>(
+        &mut self,
+        w: &Block2D<Real>,
+        eos: &EquationOfState<E>,
+    ) {
+        let wl = self.wls[Self::X2DIR].interior_x2_mut(1);
+        let wr = self.wrs[Self::X2DIR].interior_x2_mut(0);
 
-            5 => {
-                for (wl, wr, wm2, wm1, w, wp1, wp2) in
-                    izip!(wl, wr, wm2, wm1, w, wp1, wp2)
-                {
-                    for n in self.comps {
-                        unsafe {
-                            set_comp(
-                                wl,
-                                n,
-                                interp_weno5(wp2[n], wp1[n], w[n], wm1[n], wm2[n]),
-                            );
-                            set_comp(
-                                wr,
-                                n,
-                                interp_weno5(wm2[n], wm1[n], w[n], wp1[n], wp2[n]),
-                            );
-                        }
-                    }
+        let wm2 = w.interior_x2(-2);
+        let wm1 = w.interior_x2(-1);
+        let wp1 = w.interior_x2(1);
+        let wp2 = w.interior_x2(2);
+        let w = w.interior_x2(0);
+        // cells of the primitive variables, w may hold the free surface
+        let wcm2 = eos.w.interior_x2(-2);
+        let wcm1 = eos.w.interior_x2(-1);
+        let wc = eos.w.interior_x2(0);
+        let wcp1 = eos.w.interior_x2(1);
+        let wcp2 = eos.w.interior_x2(2);
+
+        for (wl, wr, wm2, wm1, w, wp1, wp2, wcm2, wcm1, wc, wcp1, wcp2) in
+            izip!(wl, wr, wm2, wm1, w, wp1, wp2, wcm2, wcm1, wc, wcp1, wcp2)
+        {
+            let stencil = self
+                .comps
+                .map(|n| [wm2[n], wm1[n], w[n], wp1[n], wp2[n]]);
+            let faces = if self.characteristic {
+                let (left, right) = eos.law.eigenvectors(wc, &self.comps, IVY);
+                self.recon.characteristic_faces(&stencil, &left, &right)
+            } else {
+                let pres = || {
+                    [wcm2, wcm1, wc, wcp1, wcp2]
+                        .map(|w| eos.law.pressure(w, &self.comps))
+                };
+                self.recon.cell_faces(&stencil, IVY, pres)
+            };
+            for (&n, (left, right)) in self.comps.iter().zip(faces) {
+                unsafe {
+                    set_comp(wr, n, left);
+                    set_comp(wl, n, right);
                 }
-            },
-
-            _ => panic!("Invalid order"),
+            }
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::*;
-    use crate::eos::eos::EquationOfState;
-    use crate::reconstruct::weno5::interp_weno5;
-    use crate::utils::defs::{Real, NHYDRO};
+    use crate::eos::{
+        eos::EquationOfState, ideal_gas::IdealGas, shallow_water::ShallowWater,
+    };
+    use crate::reconstruct::{
+        plm::{interp_plm, Limiter},
+        reconstruction::Reconstruction,
+        weno3::interp_weno3,
+        weno5::interp_weno5,
+    };
+    use crate::utils::defs::{Real, IDN, IVX, NGHOST, NHYDRO};
     use crate::hydro::hydro::Hydro;
 
     #[test]
@@ -159,7 +180,7 @@ mod tests {
         let dim2 = 5;
         let dim1 = 7;
         let mut hydro = Hydro::new(dim2, dim1);
-        let mut eos = EquationOfState::new(dim2, dim1);
+        let mut eos = EquationOfState::new(dim2, dim1, IdealGas::default());
 
         // Fill the eos with some values.
         for i in 0..eos.w.size() {
@@ -167,7 +188,7 @@ mod tests {
         }
 
         let eos = eos;
-        hydro.reconstruct_x1(&eos, 5);
+        hydro.reconstruct_x1(&eos);
 
         for n in 0..NHYDRO {
             for j in 0..dim2 as i32 {
@@ -200,7 +221,8 @@ mod tests {
             }
         }
 
-        hydro.reconstruct_x1(&eos, 3);
+        hydro.recon = Reconstruction::Weno3;
+        hydro.reconstruct_x1(&eos);
 
         for n in 0..NHYDRO {
             for j in 0..dim2 as i32 {
@@ -230,6 +252,30 @@ mod tests {
                 }
             }
         }
+
+        hydro.recon = Reconstruction::Plm(Limiter::VanLeer);
+        hydro.reconstruct_x1(&eos);
+
+        for n in 0..NHYDRO {
+            for j in 0..dim2 as i32 {
+                for i in 0..=dim1 as i32 {
+                    let wl = hydro.wls[Hydro::X1DIR].get(n, j, i);
+                    let wr = hydro.wrs[Hydro::X1DIR].get(n, j, i);
+
+                    let wm2 = eos.w.get(n, j, i - 2);
+                    let wm1 = eos.w.get(n, j, i - 1);
+                    let w = eos.w.get(n, j, i);
+                    let wp1 = eos.w.get(n, j, i + 1);
+
+                    let vl = Limiter::VanLeer;
+                    let expected_wl = interp_plm(w, wm1, wm2, vl);
+                    let expected_wr = interp_plm(wm1, w, wp1, vl);
+
+                    assert_eq!(wl, expected_wl);
+                    assert_eq!(wr, expected_wr);
+                }
+            }
+        }
     }
 
     #[test]
@@ -237,7 +283,7 @@ mod tests {
         let dim2 = 5;
         let dim1 = 7;
         let mut hydro = Hydro::new(dim2, dim1);
-        let mut eos = EquationOfState::new(dim2, dim1);
+        let mut eos = EquationOfState::new(dim2, dim1, IdealGas::default());
 
         // Fill the eos with some values.
         for i in 0..eos.w.size() {
@@ -245,7 +291,7 @@ mod tests {
         }
 
         let eos = eos;
-        hydro.reconstruct_x2(&eos, 5);
+        hydro.reconstruct_x2(&eos);
 
         for n in 0..NHYDRO {
             for j in 0..=dim2 as i32 {
@@ -278,7 +324,8 @@ mod tests {
             }
         }
 
-        hydro.reconstruct_x2(&eos, 3);
+        hydro.recon = Reconstruction::Weno3;
+        hydro.reconstruct_x2(&eos);
 
         for n in 0..NHYDRO {
             for j in 0..=dim2 as i32 {
@@ -309,4 +356,36 @@ mod tests {
             }
         }
     }
+
+    /// PPM flattening takes the pressure from the equation of state, which
+    /// is not the pressure slot of shallow water
+    #[test]
+    fn reconstruct_x1_flatten_shallow_water() {
+        let (dim2, dim1) = (1, 5);
+        let mut hydro = Hydro::new(dim2, dim1);
+        hydro.recon = Reconstruction::Ppm {
+            steepen: false,
+            flatten: true,
+        };
+        let law = ShallowWater { grav: 1.0 };
+        let mut eos = EquationOfState::new(dim2, dim1, law);
+
+        // a bore at cell 2 with the pressure slot left empty
+        eos.w.data.fill(0.0);
+        for i in -(NGHOST as i32)..(dim1 + NGHOST) as i32 {
+            let (h, u) = match i {
+                ..=1 => (4.0, 1.0),
+                2 => (2.5, 0.5),
+                _ => (1.0, 0.0),
+            };
+            *eos.w.set(IDN, 0, i) = h;
+            *eos.w.set(IVX, 0, i) = u;
+        }
+
+        hydro.reconstruct_x1(&eos);
+
+        assert_eq!(hydro.wrs[Hydro::X1DIR].get(IDN, 0, 2), 2.5);
+        assert_eq!(hydro.wls[Hydro::X1DIR].get(IDN, 0, 3), 2.5);
+        assert_eq!(hydro.wrs[Hydro::X1DIR].get(IVX, 0, 2), 0.5);
+    }
 }
@@ -0,0 +1,265 @@
+//! Implements the bottom topography of the shallow-water equations.
+//!
+//! Hydrostatic reconstruction (Audusse et al. 2004): the free surface
+//! h + zb is reconstructed instead of the height, and the heights on both
+//! sides of a face are cut at the higher of the two adjacent bottoms,
+//!
+//! h* = max(0, h + zb - max(zb_L, zb_R)).
+//!
+//! The Riemann solver sees the cut heights. The pressure removed by the cut,
+//! p(h) - p(h*), is returned to the momentum normal to the face as the
+//! bed-slope source, so a lake at rest is preserved to machine precision.
+//! The bottom is piecewise constant in each cell. Faces whose stencil
+//! reaches a dry cell fall back to the first-order states, since the free
+//! surface of a dry cell is its bottom.
+
+use itertools::iproduct;
+use crate::block::block2d::Block2D;
+use crate::coord::coordinates::Coordinates;
+use crate::eos::{
+    eos::EquationOfState, eos_trait, shallow_water::DRY_DEPTH,
+};
+use crate::utils::defs::{Real, IDN, IVX, IVY, NGHOST, NHYDRO};
+
+use crate::hydro::hydro::Hydro;
+
+/// Overwrite eta with the primitive variables w, with the height replaced
+/// by the free surface h + zb
+pub fn free_surface(
+    eta: &mut Block2D<Real>,
+    w: &Block2D<Real>,
+    zb: &Block2D<Real>,
+) {
+    eta.data.copy_from_slice(&w.data);
+    let idn = w.icomp(IDN);
+    for (eta, zb) in eta.data[idn..idn + w.len12].iter_mut().zip(&zb.data) {
+        *eta += zb;
+    }
+}
+
+impl Hydro {
+    /// Convert the reconstructed free surface on the faces in the direction
+    /// dir to the hydrostatic heights and store the pressure removed from
+    /// the left and right states
+    pub fn hydrostatic_reconstruction<E: eos_trait::EquationOfState>(
+        &mut self,
+        eos: &EquationOfState<E>,
+        zb: &Block2D<Real>,
+        dir: usize,
+    ) {
+        let (_, len2, len1) = zb.shape();
+        let nx2 = (len2 - 2 * zb.nghost) as i32;
+        let nx1 = (len1 - 2 * zb.nghost) as i32;
+
+        // offset to the cell on the left of a face
+        let (dj, di) = match dir {
+            Hydro::X1DIR => (0, 1),
+            Hydro::X2DIR => (1, 0),
+            _ => panic!("Invalid direction"),
+        };
+        let faces = iproduct!(0..nx2 + dj, 0..nx1 + di);
+
+        let mut states = [&mut self.wls[dir], &mut self.wrs[dir]];
+        let bed = &mut self.bed[dir];
+
+        let ng = NGHOST as i32;
+        for (j, i) in faces {
+            let zl = zb.get(0, j - dj, i - di);
+            let zr = zb.get(0, j, i);
+            let zf = zl.max(zr);
+
+            let dry = (-ng..ng)
+                .any(|k| eos.w.get(IDN, j + k * dj, i + k * di) <= DRY_DEPTH);
+            let cells = [(j - dj, i - di), (j, i)];
+
+            for (side, (w, z)) in states.iter_mut().zip([zl, zr]).enumerate() {
+                if dry {
+                    let (jc, ic) = cells[side];
+                    for n in 0..NHYDRO {
+                        *w.set(n, j, i) = eos.w.get(n, jc, ic);
+                    }
+                    *w.set(IDN, j, i) += z;
+                }
+
+                let eta = w.get(IDN, j, i);
+
+                *w.set(IDN, j, i) = (eta - z).max(0.0);
+                let pres = eos.pressure(w.at(j, i));
+
+                *w.set(IDN, j, i) = (eta - zf).max(0.0);
+                *bed.set(side, j, i) = pres - eos.pressure(w.at(j, i));
+            }
+        }
+    }
+
+    /// Add the bed-slope source to the conserved variables
+    ///
+    /// ```text
+    /// |  u_{0}  |
+    /// ^         ^
+    /// |         |
+    /// bed(i)    bed(i+1)
+    /// right     left
+    /// ```
+    pub fn add_bed_slope_source<E: eos_trait::EquationOfState>(
+        &self,
+        eos: &mut EquationOfState<E>,
+        coord: &Coordinates,
+        dt: Real,
+    ) {
+        if eos.zb.is_none() {
+            return;
+        }
+
+        let (_, len2, len1) = eos.u.shape();
+        let nx2 = (len2 - 2 * eos.u.nghost) as i32;
+        let nx1 = (len1 - 2 * eos.u.nghost) as i32;
+
+        for (j, i) in iproduct!(0..nx2, 0..nx1) {
+            let vol = coord.vol.get(0, j, i);
+
+            let bed = &self.bed[Hydro::X1DIR];
+            let area = &coord.area1;
+            *eos.u.set(IVX, j, i) -= dt
+                * (area.get(0, j, i + 1) * bed.get(0, j, i + 1)
+                    - area.get(0, j, i) * bed.get(1, j, i))
+                / vol;
+
+            let bed = &self.bed[Hydro::X2DIR];
+            let area = &coord.area2;
+            *eos.u.set(IVY, j, i) -= dt
+                * (area.get(0, j + 1, i) * bed.get(0, j + 1, i)
+                    - area.get(0, j, i) * bed.get(1, j, i))
+                / vol;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bvals::boundary::{BoundaryCondition, BoundaryFace};
+    use crate::coord::coordinates::CoordinateSystem;
+    use crate::eos::shallow_water::ShallowWater;
+    use crate::mesh::mesh::Mesh;
+    use crate::riemann::riemann_solver::RiemannSolver;
+    use crate::utils::defs::NGHOST;
+
+    /// A bump in the middle of the domain rising above the free surface
+    fn bump(x1: Real, x2: Real) -> Real {
+        let r2 = (x1 - 0.5).powi(2) + (x2 - 0.5).powi(2);
+        1.2 * (-r2 / 0.02).exp() + 0.3 * (6.0 * x1).sin() * (4.0 * x2).cos()
+    }
+
+    /// A lake at rest over topography with a dry island stays at rest with
+    /// the bottom in the ghost cells filled by the boundary conditions and
+    /// the ghost exchange
+    #[test]
+    fn test_lake_at_rest() {
+        let system = CoordinateSystem::Cartesian;
+        let eta = 1.0;
+
+        for (bc, riemann) in iproduct!(
+            [
+                BoundaryCondition::Outflow,
+                BoundaryCondition::Reflecting,
+                BoundaryCondition::Periodic,
+            ],
+            [RiemannSolver::RoeShallowWater, RiemannSolver::Hlle]
+        ) {
+            let mut mesh: Mesh =
+                Mesh::new(2, 2, 8, 8, system, (0.0, 1.0), (0.0, 1.0));
+            mesh.eos(ShallowWater { grav: 9.8 }).riemann_solver(riemann);
+            for face in BoundaryFace::ALL {
+                mesh.boundary(face, bc);
+            }
+
+            for pmb in mesh.meshblock.iter_mut() {
+                for (j, i) in iproduct!(0..8, 0..8) {
+                    let x1 = pmb.pcoord.x1v[i as usize + NGHOST];
+                    let x2 = pmb.pcoord.x2v[j as usize + NGHOST];
+                    let z = bump(x1, x2);
+
+                    *pmb.peos.bottom().set(0, j, i) = z;
+                    *pmb.peos.w.set(IDN, j, i) = (eta - z).max(0.0);
+                    for n in IVX..=IVY {
+                        *pmb.peos.w.set(n, j, i) = 0.0;
+                    }
+                }
+                pmb.peos.primitive_to_conserved();
+            }
+
+            for _ in 0..10 {
+                mesh.step(1e-3);
+            }
+
+            for pmb in mesh.meshblock.iter() {
+                let zb = pmb.peos.zb.as_ref().unwrap();
+                for (j, i) in iproduct!(0..8, 0..8) {
+                    let h = pmb.peos.w.get(IDN, j, i);
+                    if h > 0.0 {
+                        approx::assert_abs_diff_eq!(
+                            h + zb.get(0, j, i),
+                            eta,
+                            epsilon = 1e-12
+                        );
+                    }
+                    for n in IVX..=IVY {
+                        approx::assert_abs_diff_eq!(
+                            pmb.peos.u.get(n, j, i),
+                            0.0,
+                            epsilon = 1e-12
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// The bed-slope source balances the pressure on the sphere as well
+    #[test]
+    fn test_latlon_lake_at_rest() {
+        let pi = std::f64::consts::PI;
+        let (dim2, dim1) = (8, 16);
+        let coord = Coordinates::new(
+            CoordinateSystem::LatLon {
+                radius: 2.0,
+                omega: 0.0,
+            },
+            dim2,
+            dim1,
+            (-0.4 * pi, 0.4 * pi),
+            (0.0, 2.0 * pi),
+        );
+        let mut hydro = Hydro::new(dim2, dim1);
+        let mut eos =
+            EquationOfState::new(dim2, dim1, ShallowWater { grav: 9.8 });
+
+        let ng = NGHOST as i32;
+        for j in -ng..dim2 as i32 + ng {
+            for i in -ng..dim1 as i32 + ng {
+                let z = 0.5 * (i as Real).sin() * (j as Real).cos();
+                *eos.bottom().set(0, j, i) = z;
+                *eos.w.set(IDN, j, i) = 3.0 - z;
+            }
+        }
+
+        hydro.reconstruct_x1(&eos);
+        hydro.reconstruct_x2(&eos);
+        unsafe {
+            hydro.riemann_solver_x1(&eos, &coord);
+            hydro.riemann_solver_x2(&eos, &coord);
+        }
+
+        eos.u.data.fill(0.0);
+        hydro.add_flux_divergence(&mut eos, &coord, 1.0);
+        coord.add_coordinate_source(&mut eos, 1.0);
+        hydro.add_bed_slope_source(&mut eos, &coord, 1.0);
+
+        for u in eos.u.interior() {
+            for n in &eos.comps[IDN..=IVY] {
+                approx::assert_abs_diff_eq!(u[*n], 0.0, epsilon = 1e-12);
+            }
+        }
+    }
+}
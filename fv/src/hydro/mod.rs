@@ -1,2 +1,4 @@
+#[allow(clippy::module_inception)]
 pub mod hydro;
 pub mod hydro_reconstruct;
+pub mod hydro_topography;
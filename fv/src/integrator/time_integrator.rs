@@ -0,0 +1,219 @@
+//! Time integrator module
+//!
+//! Explicit Runge-Kutta schemes advancing the conserved variables of an
+//! EquationOfState. The conserved variable registers of EquationOfState
+//! provide the stage storage:
+//! 1. u1 holds the solution at the beginning of the step (SSP schemes)
+//! 2. u2 holds the accumulated increment (low-storage scheme)
+//!
+//! Each stage is driven by a tendency function that adds dt * L(w) to the
+//! conserved variables, where L is evaluated from the current primitive
+//! variables. The primitive variables are updated at the end of each stage.
+
+use itertools::izip;
+use std::str::FromStr;
+use crate::block::block2d::Block2D;
+use crate::eos::{eos::EquationOfState, eos_trait};
+use crate::utils::{common::set_comp, defs::Real};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TimeIntegrator {
+    ForwardEuler,
+    SspRk2,
+    SspRk3,
+    LowStorageRk4,
+}
+
+/// Coefficients of the five-stage, fourth-order 2N-storage scheme
+/// Reference: Carpenter & Kennedy (1994)
+const LSRK4_A: [Real; 5] = [
+    0.0,
+    -567301805773.0 / 1357537059087.0,
+    -2404267990393.0 / 2016746695238.0,
+    -3550918686646.0 / 2091501179385.0,
+    -1275806237668.0 / 842570457699.0,
+];
+
+const LSRK4_B: [Real; 5] = [
+    1432997174477.0 / 9575080441755.0,
+    5161836677717.0 / 13612068292357.0,
+    1720146321549.0 / 2090206949498.0,
+    3134564353537.0 / 4481467310338.0,
+    2277821191437.0 / 14882151754819.0,
+];
+
+impl TimeIntegrator {
+    pub fn nstages(&self) -> usize {
+        match self {
+            TimeIntegrator::ForwardEuler => 1,
+            TimeIntegrator::SspRk2 => 2,
+            TimeIntegrator::SspRk3 => 3,
+            TimeIntegrator::LowStorageRk4 => 5,
+        }
+    }
+
+    /// Weights (a, b, c) of the Shu-Osher stage u = a u1 + b u + c dt L(u)
+    fn weights(&self, stage: usize) -> (Real, Real, Real) {
+        match (self, stage) {
+            (TimeIntegrator::ForwardEuler, 0) => (0.0, 1.0, 1.0),
+            (TimeIntegrator::SspRk2, 0) => (0.0, 1.0, 1.0),
+            (TimeIntegrator::SspRk2, 1) => (0.5, 0.5, 0.5),
+            (TimeIntegrator::SspRk3, 0) => (0.0, 1.0, 1.0),
+            (TimeIntegrator::SspRk3, 1) => (0.75, 0.25, 0.25),
+            (TimeIntegrator::SspRk3, 2) => (1.0 / 3.0, 2.0 / 3.0, 2.0 / 3.0),
+            _ => panic!("Invalid stage"),
+        }
+    }
+
+    /// Advance the conserved variables by one stage.
+    ///
+    /// add_tendency(eos, dt) must add dt * L(w) to eos.u.
+    pub fn stage<E, F>(
+        &self,
+        stage: usize,
+        eos: &mut EquationOfState<E>,
+        dt: Real,
+        add_tendency: F,
+    ) where
+        E: eos_trait::EquationOfState,
+        F: FnOnce(&mut EquationOfState<E>, Real),
+    {
+        let comps = eos.comps;
+
+        match self {
+            TimeIntegrator::LowStorageRk4 => {
+                // u2 = A u2 + dt L(w)
+                weighted_sum(&mut eos.u2, LSRK4_A[stage], None, &comps);
+                std::mem::swap(&mut eos.u, &mut eos.u2);
+                add_tendency(eos, dt);
+                std::mem::swap(&mut eos.u, &mut eos.u2);
+
+                // u = u + B u2
+                weighted_sum(
+                    &mut eos.u,
+                    1.0,
+                    Some((LSRK4_B[stage], &eos.u2)),
+                    &comps,
+                );
+            },
+
+            _ => {
+                if stage == 0 {
+                    eos.u1.data.copy_from_slice(&eos.u.data);
+                }
+
+                let (a, b, c) = self.weights(stage);
+                weighted_sum(&mut eos.u, b, Some((a, &eos.u1)), &comps);
+                add_tendency(eos, c * dt);
+            },
+        }
+
+        eos.conserved_to_primitive();
+    }
+}
+
+impl FromStr for TimeIntegrator {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "euler" => Ok(TimeIntegrator::ForwardEuler),
+            "rk2" => Ok(TimeIntegrator::SspRk2),
+            "rk3" => Ok(TimeIntegrator::SspRk3),
+            "lsrk4" => Ok(TimeIntegrator::LowStorageRk4),
+            _ => Err(format!("Unknown time integrator: {}", name)),
+        }
+    }
+}
+
+/// u = b u + a v over the interior cells
+fn weighted_sum(
+    u: &mut Block2D<Real>,
+    b: Real,
+    v: Option<(Real, &Block2D<Real>)>,
+    comps: &[usize],
+) {
+    match v {
+        Some((a, v)) => {
+            for (u, v) in izip!(u.interior_mut(), v.interior()) {
+                for n in comps {
+                    unsafe {
+                        set_comp(u, *n, b * *u.add(*n) + a * v[*n]);
+                    }
+                }
+            }
+        },
+
+        None => {
+            for u in u.interior_mut() {
+                for n in comps {
+                    unsafe {
+                        set_comp(u, *n, b * *u.add(*n));
+                    }
+                }
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eos::ideal_gas::IdealGas;
+    use crate::utils::defs::IDN;
+
+    /// Error at t = 1 of the decay problem drho/dt = -rho
+    fn decay_error(integrator: TimeIntegrator, nsteps: usize) -> Real {
+        let mut eos = EquationOfState::new(2, 2, IdealGas::default());
+        let idn = eos.comps[IDN];
+        let dt = 1.0 / nsteps as Real;
+
+        for u in eos.u.all_mut() {
+            unsafe { set_comp(u, idn, 1.0) };
+        }
+        eos.conserved_to_primitive();
+
+        for _ in 0..nsteps {
+            for stage in 0..integrator.nstages() {
+                integrator.stage(stage, &mut eos, dt, |eos, dt| {
+                    for (u, w) in izip!(eos.u.interior_mut(), eos.w.interior())
+                    {
+                        unsafe { set_comp(u, idn, *u.add(idn) - dt * w[idn]) };
+                    }
+                });
+            }
+        }
+
+        (eos.w.get(0, 0, 0) - (-1.0 as Real).exp()).abs()
+    }
+
+    #[test]
+    fn test_order_of_accuracy() {
+        for (integrator, order) in [
+            (TimeIntegrator::ForwardEuler, 1),
+            (TimeIntegrator::SspRk2, 2),
+            (TimeIntegrator::SspRk3, 3),
+            (TimeIntegrator::LowStorageRk4, 4),
+        ] {
+            let coarse = decay_error(integrator, 10);
+            let fine = decay_error(integrator, 20);
+            let rate = (coarse / fine).log2();
+
+            assert!(
+                (rate - order as Real).abs() < 0.2,
+                "{:?}: rate = {}",
+                integrator,
+                rate
+            );
+        }
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!(
+            "rk3".parse::<TimeIntegrator>(),
+            Ok(TimeIntegrator::SspRk3)
+        );
+        assert!("rk9".parse::<TimeIntegrator>().is_err());
+    }
+}
@@ -1,9 +1,14 @@
 pub mod utils;
 pub mod block;
+pub mod bvals;
+pub mod coord;
 pub mod eos;
 pub mod hydro;
+pub mod integrator;
+pub mod mesh;
 pub mod riemann;
 pub mod reconstruct;
+pub mod thermo;
 
 use pyo3::prelude::*;
 
@@ -0,0 +1,436 @@
+//! Cubed-sphere mesh
+//!
+//! Six MeshBlocks, one per panel of the equiangular gnomonic projection.
+//! Each ghost cell is interpolated from the interior cells of the
+//! neighboring panel that contains its center. Across the shared edge the
+//! ghost cell centers fall on the cell centers of the neighboring panel,
+//! along the edge they fall between them and are interpolated by a cubic
+//! through the nearest four cells. Velocity components are rotated from the
+//! basis of each source cell to the basis of the destination panel.
+
+use crate::bvals::boundary::{BoundaryCondition, BoundaryValues};
+use crate::coord::coordinates::{CoordinateSystem, Coordinates};
+use crate::coord::gnomonic::{self, NPANEL};
+use crate::eos::eos_trait;
+use crate::integrator::time_integrator::TimeIntegrator;
+use crate::mesh::mesh::{GhostCopy, Mesh, MeshBlock};
+use crate::utils::defs::{Real, NGHOST};
+use std::f64::consts::FRAC_PI_4;
+
+// number of cells interpolated along a panel edge
+const ORDER: usize = 4;
+
+impl<E: eos_trait::EquationOfState> Mesh<E> {
+    /// Cubed sphere of given radius and rotation rate with nx by nx cells
+    /// per panel
+    pub fn cubed_sphere(nx: usize, radius: Real, omega: Real) -> Self {
+        let meshblock: Vec<_> = (0..NPANEL)
+            .map(|panel| {
+                let mut pmb = MeshBlock::new(nx, nx);
+                pmb.coord(Box::new(Coordinates::new(
+                    CoordinateSystem::CubedSphere {
+                        radius,
+                        omega,
+                        panel,
+                    },
+                    nx,
+                    nx,
+                    (-FRAC_PI_4, FRAC_PI_4),
+                    (-FRAC_PI_4, FRAC_PI_4),
+                )));
+                pmb.bvals = BoundaryValues::new(BoundaryCondition::Block);
+                pmb
+            })
+            .collect();
+
+        let ghost = cubed_sphere_ghosts(&meshblock);
+
+        Self {
+            nb2: 1,
+            nb1: NPANEL,
+            periodic: [false, false],
+            meshblock,
+            ghost,
+            integrator: TimeIntegrator::SspRk3,
+        }
+    }
+}
+
+fn cubed_sphere_ghosts<E: eos_trait::EquationOfState>(
+    meshblock: &[MeshBlock<E>],
+) -> Vec<GhostCopy> {
+    let mut ghost = Vec::new();
+
+    for (panel, pmb) in meshblock.iter().enumerate() {
+        let (nx2, nx1) = (pmb.nx2 as i32, pmb.nx1 as i32);
+        let ng = NGHOST as i32;
+
+        for j in -ng..nx2 + ng {
+            for i in -ng..nx1 + ng {
+                let inside1 = (0..nx1).contains(&i);
+                let inside2 = (0..nx2).contains(&j);
+
+                // interior cells and corner ghost cells are not exchanged
+                if inside1 == inside2 {
+                    continue;
+                }
+
+                let coord = &pmb.pcoord;
+                let xi = coord.x1v[(i + ng) as usize];
+                let eta = coord.x2v[(j + ng) as usize];
+                let r = gnomonic::to_cartesian(panel, xi, eta);
+
+                let src = gnomonic::find_panel(&r);
+                let (xi_src, eta_src) = gnomonic::from_cartesian(src, &r);
+                let scoord = &meshblock[src].pcoord;
+                let fi = cell_position(scoord.dx1f[0], xi_src);
+                let fj = cell_position(scoord.dx2f[0], eta_src);
+
+                // interpolate along the edge, in the direction whose
+                // position falls between the cell centers
+                let stencil: Vec<_> =
+                    if (fi - fi.round()).abs() > (fj - fj.round()).abs() {
+                        let js = (fj.round() as i32).clamp(0, nx2 - 1);
+                        let (is, weight) = lagrange(nx1, fi);
+                        (0..ORDER)
+                            .map(|k| ((js, is + k as i32), weight[k]))
+                            .collect()
+                    } else {
+                        let is = (fi.round() as i32).clamp(0, nx1 - 1);
+                        let (js, weight) = lagrange(nx2, fj);
+                        (0..ORDER)
+                            .map(|k| ((js + k as i32, is), weight[k]))
+                            .collect()
+                    };
+
+                let e_dst = gnomonic::basis(panel, xi, eta);
+                for ((js, is), weight) in stencil {
+                    let e_src = gnomonic::basis(
+                        src,
+                        scoord.x1v[(is + ng) as usize],
+                        scoord.x2v[(js + ng) as usize],
+                    );
+                    let c1 = gnomonic::to_components(&e_dst, &e_src[0]);
+                    let c2 = gnomonic::to_components(&e_dst, &e_src[1]);
+
+                    ghost.push(GhostCopy {
+                        dst: (panel, j, i),
+                        src: (src, js, is),
+                        rot: [[c1[0], c2[0]], [c1[1], c2[1]]],
+                        weight,
+                    });
+                }
+            }
+        }
+    }
+
+    ghost
+}
+
+/// Position of the angle x in units of the cells of a panel, integral at
+/// the cell centers
+fn cell_position(dx: Real, x: Real) -> Real {
+    (x + FRAC_PI_4) / dx - 0.5
+}
+
+/// First cell and Lagrange weights of the ORDER cells of a panel of nx
+/// cells interpolating at the position x, the stencil kept on the panel
+fn lagrange(nx: i32, x: Real) -> (i32, [Real; ORDER]) {
+    let first = (x.floor() as i32 - (ORDER as i32 - 1) / 2)
+        .clamp(0, nx - ORDER as i32);
+
+    let node = |k: usize| (first + k as i32) as Real;
+    let weight = std::array::from_fn(|k| {
+        (0..ORDER)
+            .filter(|m| *m != k)
+            .map(|m| (x - node(m)) / (node(k) - node(m)))
+            .product()
+    });
+
+    (first, weight)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eos::shallow_water::ShallowWater;
+    use crate::riemann::riemann_solver::RiemannSolver;
+    use crate::utils::defs::{IDN, IVX, IVY};
+    use itertools::iproduct;
+
+    #[test]
+    fn test_total_area() {
+        let mesh: Mesh = Mesh::cubed_sphere(6, 2.0, 0.0);
+
+        let area: Real = mesh
+            .meshblock
+            .iter()
+            .flat_map(|pmb| pmb.pcoord.vol.interior().map(|v| v[0]))
+            .sum();
+
+        approx::assert_abs_diff_eq!(
+            area,
+            16.0 * std::f64::consts::PI,
+            epsilon = 1e-12
+        );
+    }
+
+    /// Solid-body rotation about an axis tilted from the pole and a smooth
+    /// height field on the unit sphere
+    fn solid_body(r: &gnomonic::Vec3) -> (Real, gnomonic::Vec3) {
+        let axis = [0.0, 0.6, 0.8];
+        (1.0 + 0.2 * r[0] * r[2], gnomonic::cross(&axis, r))
+    }
+
+    /// Maximum deviation of the exchanged ghost cells from the analytic
+    /// solid-body rotation evaluated at the ghost cell centers
+    fn exchange_error(nx: usize) -> Real {
+        let mut mesh: Mesh = Mesh::cubed_sphere(nx, 1.0, 0.0);
+        let n = nx as i32;
+
+        for (panel, pmb) in mesh.meshblock.iter_mut().enumerate() {
+            for (j, i) in iproduct!(0..n, 0..n) {
+                let xi = pmb.pcoord.x1v[(i + NGHOST as i32) as usize];
+                let eta = pmb.pcoord.x2v[(j + NGHOST as i32) as usize];
+                let r = gnomonic::to_cartesian(panel, xi, eta);
+                let (h, v) = solid_body(&r);
+                let c = gnomonic::to_components(
+                    &gnomonic::basis(panel, xi, eta),
+                    &v,
+                );
+
+                *pmb.peos.w.set(0, j, i) = h;
+                *pmb.peos.w.set(IVX, j, i) = c[0];
+                *pmb.peos.w.set(IVY, j, i) = c[1];
+            }
+        }
+
+        mesh.exchange_ghosts();
+
+        let mut error: Real = 0.0;
+        for copy in mesh.ghost.iter() {
+            let (panel, j, i) = copy.dst;
+            let pmb = &mesh.meshblock[panel];
+            let xi = pmb.pcoord.x1v[(i + NGHOST as i32) as usize];
+            let eta = pmb.pcoord.x2v[(j + NGHOST as i32) as usize];
+            let r = gnomonic::to_cartesian(panel, xi, eta);
+            let (h, v) = solid_body(&r);
+            let c =
+                gnomonic::to_components(&gnomonic::basis(panel, xi, eta), &v);
+
+            error = error
+                .max((pmb.peos.w.get(0, j, i) - h).abs())
+                .max((pmb.peos.w.get(IVX, j, i) - c[0]).abs())
+                .max((pmb.peos.w.get(IVY, j, i) - c[1]).abs());
+        }
+
+        error
+    }
+
+    #[test]
+    fn test_solid_body_rotation_exchange() {
+        let coarse = exchange_error(8);
+        let fine = exchange_error(16);
+
+        assert!(coarse < 1e-3, "error = {}", coarse);
+        assert!(fine < 0.15 * coarse, "{} vs {}", fine, coarse);
+    }
+
+    /// Maximum momentum tendency of Williamson et al. (1992) test case 2,
+    /// a steady zonal flow in geostrophic balance
+    fn williamson2_residual(nx: usize) -> Real {
+        let (radius, omega, grav) = (1.0, 1.0, 2.0);
+        let (h0, u0) = (2.0, 0.2);
+        let mut mesh: Mesh = Mesh::cubed_sphere(nx, radius, omega);
+        mesh.eos(ShallowWater { grav });
+        let n = nx as i32;
+
+        for (panel, pmb) in mesh.meshblock.iter_mut().enumerate() {
+            for (j, i) in iproduct!(0..n, 0..n) {
+                let xi = pmb.pcoord.x1v[(i + NGHOST as i32) as usize];
+                let eta = pmb.pcoord.x2v[(j + NGHOST as i32) as usize];
+                let r = gnomonic::to_cartesian(panel, xi, eta);
+                let v = gnomonic::cross(&[0.0, 0.0, u0], &r);
+                let c = gnomonic::to_components(
+                    &gnomonic::basis(panel, xi, eta),
+                    &v,
+                );
+                let h = h0
+                    - (radius * omega * u0 + 0.5 * u0 * u0) * r[2] * r[2]
+                        / grav;
+
+                *pmb.peos.w.set(0, j, i) = h;
+                *pmb.peos.w.set(IVX, j, i) = c[0];
+                *pmb.peos.w.set(IVY, j, i) = c[1];
+            }
+        }
+
+        mesh.exchange_ghosts();
+
+        let mut residual: Real = 0.0;
+        for pmb in mesh.meshblock.iter_mut() {
+            pmb.hydro.reconstruct_x1(&pmb.peos);
+            pmb.hydro.reconstruct_x2(&pmb.peos);
+            unsafe {
+                pmb.hydro.riemann_solver_x1(&pmb.peos, &pmb.pcoord);
+                pmb.hydro.riemann_solver_x2(&pmb.peos, &pmb.pcoord);
+            }
+
+            pmb.peos.u.data.fill(0.0);
+            pmb.hydro
+                .add_flux_divergence(&mut pmb.peos, &pmb.pcoord, 1.0);
+            pmb.pcoord.add_coordinate_source(&mut pmb.peos, 1.0);
+
+            for (j, i) in iproduct!(0..n, 0..n) {
+                for m in [IVX, IVY] {
+                    residual = residual.max(pmb.peos.u.get(m, j, i).abs());
+                }
+            }
+        }
+
+        residual
+    }
+
+    /// The curvature terms, the skewed faces and the interpolated ghost
+    /// cells keep the zonal flow steady across the panel edges
+    #[test]
+    fn test_geostrophic_balance() {
+        let coarse = williamson2_residual(16);
+        let fine = williamson2_residual(32);
+
+        assert!(coarse < 2e-3, "residual = {}", coarse);
+        assert!(fine < 0.35 * coarse, "{} vs {}", fine, coarse);
+    }
+
+    /// Advects a cosine bell once around the sphere in the solid-body
+    /// wind of Williamson et al. (1992) test case 1 and returns the L1
+    /// (relative to the bell) and L-infinity errors of the height. The
+    /// wind is prescribed, so it is reset after every step; the bell
+    /// starts at a cube corner to cross the panel edges and corners.
+    fn cosine_bell_error(nx: usize) -> (Real, Real) {
+        let (radius, u0) = (1.0, 1.0);
+        let mut mesh: Mesh =
+            Mesh::cubed_sphere(nx, radius, -0.5 * u0 / radius);
+        mesh.eos(ShallowWater { grav: 0.0 });
+        mesh.riemann_solver(RiemannSolver::Hlle);
+        let n = nx as i32;
+
+        let center = {
+            let lat = (0.5 as Real).sqrt().atan();
+            [lat.cos(), 0.0, lat.sin()]
+        };
+        let bell = |r: &gnomonic::Vec3| {
+            let rb = 1.0 / 3.0;
+            let d = gnomonic::dot(r, &center).clamp(-1.0, 1.0).acos();
+            if d < rb {
+                0.5 * (1.0 + (std::f64::consts::PI * d / rb).cos())
+            } else {
+                0.0
+            }
+        };
+        let position = |panel: usize, pmb: &MeshBlock<ShallowWater>, j, i| {
+            let xi = pmb.pcoord.x1v[(i + NGHOST as i32) as usize];
+            let eta = pmb.pcoord.x2v[(j + NGHOST as i32) as usize];
+            (xi, eta, gnomonic::to_cartesian(panel, xi, eta))
+        };
+        let set_wind = |mesh: &mut Mesh| {
+            for (panel, pmb) in mesh.meshblock.iter_mut().enumerate() {
+                for (j, i) in iproduct!(0..n, 0..n) {
+                    let (xi, eta, r) = position(panel, pmb, j, i);
+                    let v = gnomonic::cross(&[0.0, 0.0, u0 / radius], &r);
+                    let c = gnomonic::to_components(
+                        &gnomonic::basis(panel, xi, eta),
+                        &v,
+                    );
+                    *pmb.peos.w.set(IVX, j, i) = c[0];
+                    *pmb.peos.w.set(IVY, j, i) = c[1];
+                }
+                pmb.peos.primitive_to_conserved();
+            }
+        };
+
+        for (panel, pmb) in mesh.meshblock.iter_mut().enumerate() {
+            for (j, i) in iproduct!(0..n, 0..n) {
+                let (_, _, r) = position(panel, pmb, j, i);
+                *pmb.peos.w.set(0, j, i) = 1.0 + bell(&r);
+            }
+        }
+        set_wind(&mut mesh);
+
+        let period = 2.0 * std::f64::consts::PI * radius / u0;
+        let mut time = 0.0;
+        while time < period {
+            let dt = mesh.new_dt(0.4).min(period - time);
+            mesh.step(dt);
+            set_wind(&mut mesh);
+            time += dt;
+        }
+
+        let (mut l1, mut norm, mut linf): (Real, Real, Real) = (0.0, 0.0, 0.0);
+        for (panel, pmb) in mesh.meshblock.iter().enumerate() {
+            for (j, i) in iproduct!(0..n, 0..n) {
+                let (_, _, r) = position(panel, pmb, j, i);
+                let vol = pmb.pcoord.vol.get(0, j, i);
+                let error = (pmb.peos.w.get(0, j, i) - 1.0 - bell(&r)).abs();
+
+                l1 += error * vol;
+                norm += bell(&r) * vol;
+                linf = linf.max(error);
+            }
+        }
+        (l1 / norm, linf)
+    }
+
+    /// The bell crosses the panel edges and corners without losing its
+    /// shape, and the error converges with the resolution
+    #[test]
+    fn test_cosine_bell() {
+        let (coarse_l1, coarse_linf) = cosine_bell_error(16);
+        let (fine_l1, fine_linf) = cosine_bell_error(24);
+
+        assert!(coarse_l1 < 0.62, "l1 error = {}", coarse_l1);
+        assert!(coarse_linf < 0.46, "linf error = {}", coarse_linf);
+        assert!(fine_l1 < 0.45 * coarse_l1, "{} vs {}", fine_l1, coarse_l1);
+        assert!(
+            fine_linf < 0.45 * coarse_linf,
+            "{} vs {}",
+            fine_linf,
+            coarse_linf
+        );
+    }
+
+    #[test]
+    fn test_lake_at_rest() {
+        let nx = 8;
+        let mut mesh: Mesh = Mesh::cubed_sphere(nx, 1.0, 2.0);
+        mesh.eos(ShallowWater { grav: 9.8 });
+
+        for pmb in mesh.meshblock.iter_mut() {
+            for (j, i) in iproduct!(0..nx as i32, 0..nx as i32) {
+                *pmb.peos.w.set(0, j, i) = 2.0;
+            }
+        }
+
+        mesh.exchange_ghosts();
+
+        for pmb in mesh.meshblock.iter_mut() {
+            pmb.hydro.reconstruct_x1(&pmb.peos);
+            pmb.hydro.reconstruct_x2(&pmb.peos);
+            unsafe {
+                pmb.hydro.riemann_solver_x1(&pmb.peos, &pmb.pcoord);
+                pmb.hydro.riemann_solver_x2(&pmb.peos, &pmb.pcoord);
+            }
+
+            pmb.peos.u.data.fill(0.0);
+            pmb.hydro
+                .add_flux_divergence(&mut pmb.peos, &pmb.pcoord, 1.0);
+            pmb.pcoord.add_coordinate_source(&mut pmb.peos, 1.0);
+
+            for u in pmb.peos.u.interior() {
+                for n in &pmb.hydro.comps[IDN..=IVY] {
+                    approx::assert_abs_diff_eq!(u[*n], 0.0, epsilon = 1e-12);
+                }
+            }
+        }
+    }
+}
@@ -6,15 +6,28 @@
 //! The Mesh struct contains a vector of MeshBlocks.
 //! The Mesh struct is the main struct that manages communication between
 //! MeshBlocks.
-use crate::eos::eos::EquationOfState;
+use crate::bvals::boundary::{
+    BoundaryCondition, BoundaryFace, BoundaryValues,
+};
+use crate::coord::coordinates::{CoordinateSystem, Coordinates};
+use crate::eos::{
+    eos::EquationOfState, eos_trait, shallow_water::ShallowWater,
+};
+use crate::hydro::hydro::Hydro;
+use crate::integrator::time_integrator::TimeIntegrator;
+use crate::reconstruct::reconstruction::Reconstruction;
+use crate::riemann::riemann_solver::RiemannSolver;
+use crate::utils::defs::{Real, IVX, IVY, NGHOST, NHYDRO};
 
-pub struct MeshBlock {
-    nx3: usize,
-    nx2: usize,
-    nx1: usize,
+pub struct MeshBlock<E: eos_trait::EquationOfState = ShallowWater> {
+    pub nx2: usize,
+    pub nx1: usize,
+
+    pub bvals: BoundaryValues,
 
     // TODO: Add other modules
-    pub peos: Box<EquationOfState>,
+    pub pcoord: Box<Coordinates>,
+    pub peos: Box<EquationOfState<E>>,
     pub hydro: Box<Hydro>,
     // field: Field,
     // rad: Radiation,
@@ -22,34 +35,936 @@ pub struct MeshBlock {
     // chem: Chemistry,
 }
 
-impl Default for MeshBlock {
-    fn default() -> Self {
+impl<E: eos_trait::EquationOfState> MeshBlock<E> {
+    /// Create a MeshBlock on a unit Cartesian square with the default
+    /// parameters of the equation of state
+    pub fn new(nx2: usize, nx1: usize) -> Self {
+        let pcoord = Box::new(Coordinates::new(
+            CoordinateSystem::Cartesian,
+            nx2,
+            nx1,
+            (0.0, 1.0),
+            (0.0, 1.0),
+        ));
+        let peos = Box::new(EquationOfState::new(nx2, nx1, E::default()));
+        let hydro = Box::new(Hydro::new(nx2, nx1));
         Self {
-            nx3: 0,
-            nx2: 0,
-            nx1: 0,
-            peos: Box::new(EquationOfState::default()),
+            nx2,
+            nx1,
+            bvals: BoundaryValues::new(BoundaryCondition::Outflow),
+            pcoord,
+            peos,
+            hydro,
         }
     }
+
+    pub fn coord(&mut self, coord: Box<Coordinates>) -> &mut Self {
+        self.pcoord = coord;
+        self
+    }
+
+    pub fn eos(&mut self, eos: Box<EquationOfState<E>>) -> &mut Self {
+        self.peos = eos;
+        self
+    }
+
+    pub fn reconstruction(&mut self, recon: Reconstruction) -> &mut Self {
+        self.hydro.recon = recon;
+        self
+    }
+
+    pub fn riemann_solver(&mut self, riemann: RiemannSolver) -> &mut Self {
+        self.hydro.riemann = riemann;
+        self
+    }
+
+    pub fn characteristic(&mut self, characteristic: bool) -> &mut Self {
+        self.hydro.characteristic = characteristic;
+        self
+    }
+
+    /// Fill the ghost cells at the physical boundaries of this MeshBlock,
+    /// including those of the bottom elevation
+    pub fn apply_boundary(&mut self) {
+        self.bvals.apply(&mut self.peos.w, &self.pcoord);
+        if let Some(zb) = self.peos.zb.as_mut() {
+            self.bvals.apply_scalar(zb);
+        }
+    }
+
+    /// Maximum stable time step of this MeshBlock
+    pub fn new_dt(&self, cfl: Real) -> Real {
+        self.hydro.new_dt(&self.peos, &self.pcoord, cfl)
+    }
+
+    /// Advance the conserved variables by one stage of the time integrator
+    /// and update the primitive variables. The ghost cells of the primitive
+    /// variables must be filled.
+    pub fn stage(
+        &mut self,
+        integrator: TimeIntegrator,
+        stage: usize,
+        dt: Real,
+    ) {
+        self.hydro.reconstruct_x1(&self.peos);
+        self.hydro.reconstruct_x2(&self.peos);
+
+        unsafe {
+            self.hydro.riemann_solver_x1(&self.peos, &self.pcoord);
+            self.hydro.riemann_solver_x2(&self.peos, &self.pcoord);
+        }
+
+        let hydro = &self.hydro;
+        let pcoord = &self.pcoord;
+        integrator.stage(stage, &mut self.peos, dt, |eos, dt| {
+            hydro.add_flux_divergence(eos, pcoord, dt);
+            pcoord.add_coordinate_source(eos, dt);
+            hydro.add_bed_slope_source(eos, pcoord, dt);
+        });
+    }
 }
 
-impl MeshBlock {
-    pub fn new(nx2: usize, nx1: usize) -> Self {
-        let peos = Box::new(EquationOfState::new(nx2, nx1));
+/// Weighted copy of an interior cell of one MeshBlock into a ghost cell of
+/// another. The horizontal velocity components are transformed by the
+/// matrix rot. A ghost cell is the sum of all copies into it, so that it
+/// can be interpolated from several cells.
+pub struct GhostCopy {
+    pub dst: (usize, i32, i32),
+    pub src: (usize, i32, i32),
+    pub rot: [[Real; 2]; 2],
+    pub weight: Real,
+}
+
+pub struct Mesh<E: eos_trait::EquationOfState = ShallowWater> {
+    // number of MeshBlocks in the x2 and x1 directions
+    pub nb2: usize,
+    pub nb1: usize,
+
+    // periodic in the x1 and x2 directions
+    pub periodic: [bool; 2],
+
+    pub meshblock: Vec<MeshBlock<E>>,
+    pub ghost: Vec<GhostCopy>,
+    pub integrator: TimeIntegrator,
+}
+
+impl<E: eos_trait::EquationOfState> Mesh<E> {
+    /// Decompose the domain [x2min, x2max] x [x1min, x1max] into nb2 by nb1
+    /// MeshBlocks of nx2 by nx1 cells each
+    pub fn new(
+        nb2: usize,
+        nb1: usize,
+        nx2: usize,
+        nx1: usize,
+        system: CoordinateSystem,
+        x2lim: (Real, Real),
+        x1lim: (Real, Real),
+    ) -> Self {
+        let dx2 = (x2lim.1 - x2lim.0) / nb2 as Real;
+        let dx1 = (x1lim.1 - x1lim.0) / nb1 as Real;
+
+        let mut meshblock = Vec::with_capacity(nb2 * nb1);
+        for jb in 0..nb2 {
+            for ib in 0..nb1 {
+                let x2min = x2lim.0 + jb as Real * dx2;
+                let x1min = x1lim.0 + ib as Real * dx1;
+
+                let mut pmb = MeshBlock::new(nx2, nx1);
+                pmb.coord(Box::new(Coordinates::new(
+                    system,
+                    nx2,
+                    nx1,
+                    (x2min, x2min + dx2),
+                    (x1min, x1min + dx1),
+                )));
+
+                // faces shared with other MeshBlocks
+                let block = BoundaryCondition::Block;
+                if ib > 0 {
+                    pmb.bvals.set(BoundaryFace::InnerX1, block);
+                }
+                if ib + 1 < nb1 {
+                    pmb.bvals.set(BoundaryFace::OuterX1, block);
+                }
+                if jb > 0 {
+                    pmb.bvals.set(BoundaryFace::InnerX2, block);
+                }
+                if jb + 1 < nb2 {
+                    pmb.bvals.set(BoundaryFace::OuterX2, block);
+                }
+
+                meshblock.push(pmb);
+            }
+        }
+
+        let periodic = [false, false];
+        let ghost =
+            neighbor_ghosts((nb2, nb1), (nx2 as i32, nx1 as i32), periodic);
+
         Self {
-            nx1,
-            nx2,
-            nx3,
-            peos,
+            nb2,
+            nb1,
+            periodic,
+            meshblock,
+            ghost,
+            integrator: TimeIntegrator::SspRk3,
         }
     }
 
-    pub fn eos(&mut self, eos: Box<EquationOfState>) -> &mut Self {
-        self.peos = eos;
+    /// Set the boundary condition of a face of the domain. Periodic boundary
+    /// conditions apply to both faces of a direction. A non-periodic
+    /// condition on a face of a periodic direction breaks the pairing and
+    /// also applies to the opposite face until that face is set.
+    pub fn boundary(
+        &mut self,
+        face: BoundaryFace,
+        bc: BoundaryCondition,
+    ) -> &mut Self {
+        let (dir, faces) = match face {
+            BoundaryFace::InnerX1 | BoundaryFace::OuterX1 => {
+                (0, [BoundaryFace::InnerX1, BoundaryFace::OuterX1])
+            },
+            BoundaryFace::InnerX2 | BoundaryFace::OuterX2 => {
+                (1, [BoundaryFace::InnerX2, BoundaryFace::OuterX2])
+            },
+        };
+
+        let periodic = matches!(bc, BoundaryCondition::Periodic);
+        let (faces, bc) = match (periodic, self.periodic[dir]) {
+            (true, true) => return self,
+            (true, false) => (&faces[..], BoundaryCondition::Block),
+            (false, true) => (&faces[..], bc),
+            (false, false) => (std::slice::from_ref(&face), bc),
+        };
+
+        let (nb2, nb1) = (self.nb2, self.nb1);
+        for (b, pmb) in self.meshblock.iter_mut().enumerate() {
+            let (jb, ib) = (b / nb1, b % nb1);
+            for &face in faces {
+                let on_domain = match face {
+                    BoundaryFace::InnerX1 => ib == 0,
+                    BoundaryFace::OuterX1 => ib + 1 == nb1,
+                    BoundaryFace::InnerX2 => jb == 0,
+                    BoundaryFace::OuterX2 => jb + 1 == nb2,
+                };
+                if on_domain {
+                    pmb.bvals.set(face, bc);
+                }
+            }
+        }
+
+        if periodic != self.periodic[dir] {
+            self.periodic[dir] = periodic;
+            let pmb = &self.meshblock[0];
+            self.ghost = neighbor_ghosts(
+                (self.nb2, self.nb1),
+                (pmb.nx2 as i32, pmb.nx1 as i32),
+                self.periodic,
+            );
+        }
+
+        self
+    }
+
+    /// Set the equation of state of all MeshBlocks
+    pub fn eos(&mut self, law: E) -> &mut Self {
+        for pmb in self.meshblock.iter_mut() {
+            pmb.peos.law = law.clone();
+        }
+        self
+    }
+
+    pub fn integrator(&mut self, integrator: TimeIntegrator) -> &mut Self {
+        self.integrator = integrator;
+        self
+    }
+
+    /// Set the reconstruction of all MeshBlocks
+    pub fn reconstruction(&mut self, recon: Reconstruction) -> &mut Self {
+        for pmb in self.meshblock.iter_mut() {
+            pmb.reconstruction(recon);
+        }
         self
     }
+
+    /// Set the Riemann solver of all MeshBlocks
+    pub fn riemann_solver(&mut self, riemann: RiemannSolver) -> &mut Self {
+        for pmb in self.meshblock.iter_mut() {
+            pmb.riemann_solver(riemann);
+        }
+        self
+    }
+
+    /// Reconstruct in characteristic variables on all MeshBlocks
+    pub fn characteristic(&mut self, characteristic: bool) -> &mut Self {
+        for pmb in self.meshblock.iter_mut() {
+            pmb.characteristic(characteristic);
+        }
+        self
+    }
+
+    /// Maximum stable time step over all MeshBlocks
+    pub fn new_dt(&self, cfl: Real) -> Real {
+        self.meshblock
+            .iter()
+            .map(|pmb| pmb.new_dt(cfl))
+            .fold(Real::MAX, Real::min)
+    }
+
+    /// Advance all MeshBlocks by dt, ending with the saturation adjustment
+    /// of the equation of state
+    pub fn step(&mut self, dt: Real) {
+        for stage in 0..self.integrator.nstages() {
+            self.exchange_ghosts();
+
+            for pmb in self.meshblock.iter_mut() {
+                pmb.apply_boundary();
+                pmb.stage(self.integrator, stage, dt);
+            }
+        }
+
+        for pmb in self.meshblock.iter_mut() {
+            pmb.peos.saturation_adjustment();
+        }
+    }
+
+    /// Fill the ghost cells of the primitive variables and the bottom
+    /// elevation from the neighboring MeshBlocks
+    pub fn exchange_ghosts(&mut self) {
+        let (iv1, iv2) = (IVX, IVY);
+
+        let buffer: Vec<[Real; NHYDRO]> = self
+            .ghost
+            .iter()
+            .map(|copy| {
+                let (b, j, i) = copy.src;
+                let w = &self.meshblock[b].peos.w;
+
+                let mut val = [0.0; NHYDRO];
+                for (n, v) in val.iter_mut().enumerate() {
+                    *v = w.get(n, j, i);
+                }
+
+                let (v1, v2) = (val[iv1], val[iv2]);
+                val[iv1] = copy.rot[0][0] * v1 + copy.rot[0][1] * v2;
+                val[iv2] = copy.rot[1][0] * v1 + copy.rot[1][1] * v2;
+                val.map(|v| copy.weight * v)
+            })
+            .collect();
+
+        for copy in self.ghost.iter() {
+            let (b, j, i) = copy.dst;
+            for n in 0..NHYDRO {
+                *self.meshblock[b].peos.w.set(n, j, i) = 0.0;
+            }
+        }
+
+        for (copy, val) in self.ghost.iter().zip(buffer) {
+            let (b, j, i) = copy.dst;
+            let w = &mut self.meshblock[b].peos.w;
+
+            for (n, v) in val.iter().enumerate() {
+                *w.set(n, j, i) += *v;
+            }
+        }
+
+        // bottom elevation
+        let bottom: Vec<Option<Real>> = self
+            .ghost
+            .iter()
+            .map(|copy| {
+                let (b, j, i) = copy.src;
+                let zb = self.meshblock[b].peos.zb.as_ref();
+                zb.map(|zb| copy.weight * zb.get(0, j, i))
+            })
+            .collect();
+
+        for (copy, z) in self.ghost.iter().zip(&bottom) {
+            let (b, j, i) = copy.dst;
+            if let (Some(_), Some(zb)) = (z, self.meshblock[b].peos.zb.as_mut())
+            {
+                *zb.set(0, j, i) = 0.0;
+            }
+        }
+
+        for (copy, z) in self.ghost.iter().zip(bottom) {
+            let (b, j, i) = copy.dst;
+            if let (Some(z), Some(zb)) = (z, self.meshblock[b].peos.zb.as_mut())
+            {
+                *zb.set(0, j, i) += z;
+            }
+        }
+    }
 }
 
-pub struct Mesh {
-    meshblock: Vec<MeshBlock>,
+/// Ghost cells shared between adjacent MeshBlocks of a regular layout of
+/// nb2 by nb1 MeshBlocks, wrapping around in the periodic directions
+fn neighbor_ghosts(
+    nb: (usize, usize),
+    nx: (i32, i32),
+    periodic: [bool; 2],
+) -> Vec<GhostCopy> {
+    let ((nb2, nb1), (nx2, nx1)) = (nb, nx);
+    let identity = [[1.0, 0.0], [0.0, 1.0]];
+    let ng = NGHOST as i32;
+    let mut ghost = Vec::new();
+
+    // neighboring block index in a direction with nb blocks
+    let prev = |ib: usize, nb: usize, periodic: bool| match ib {
+        0 if periodic => Some(nb - 1),
+        0 => None,
+        _ => Some(ib - 1),
+    };
+    let next = |ib: usize, nb: usize, periodic: bool| match ib + 1 {
+        n if n < nb => Some(n),
+        _ if periodic => Some(0),
+        _ => None,
+    };
+
+    for jb in 0..nb2 {
+        for ib in 0..nb1 {
+            let b = jb * nb1 + ib;
+
+            for j in 0..nx2 {
+                for k in 0..ng {
+                    if let Some(src) = prev(ib, nb1, periodic[0]) {
+                        ghost.push(GhostCopy {
+                            dst: (b, j, -1 - k),
+                            src: (jb * nb1 + src, j, nx1 - 1 - k),
+                            rot: identity,
+                            weight: 1.0,
+                        });
+                    }
+                    if let Some(src) = next(ib, nb1, periodic[0]) {
+                        ghost.push(GhostCopy {
+                            dst: (b, j, nx1 + k),
+                            src: (jb * nb1 + src, j, k),
+                            rot: identity,
+                            weight: 1.0,
+                        });
+                    }
+                }
+            }
+
+            for i in 0..nx1 {
+                for k in 0..ng {
+                    if let Some(src) = prev(jb, nb2, periodic[1]) {
+                        ghost.push(GhostCopy {
+                            dst: (b, -1 - k, i),
+                            src: (src * nb1 + ib, nx2 - 1 - k, i),
+                            rot: identity,
+                            weight: 1.0,
+                        });
+                    }
+                    if let Some(src) = next(jb, nb2, periodic[1]) {
+                        ghost.push(GhostCopy {
+                            dst: (b, nx2 + k, i),
+                            src: (src * nb1 + ib, k, i),
+                            rot: identity,
+                            weight: 1.0,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    ghost
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eos::ideal_gas::IdealGas;
+    use crate::riemann::exact::ExactRiemann;
+    use crate::utils::{
+        common::dry_state,
+        defs::{IDN, IPR},
+    };
+
+    #[test]
+    fn meshblock_new() {
+        let mut pmb: MeshBlock = MeshBlock::new(4, 6);
+
+        let (len2, len1) = (4 + 2 * NGHOST, 6 + 2 * NGHOST);
+        assert_eq!(pmb.peos.w.shape(), (NHYDRO, len2, len1));
+        assert_eq!(pmb.pcoord.vol.shape(), (1, len2, len1));
+
+        pmb.coord(Box::new(Coordinates::new(
+            CoordinateSystem::Cylindrical,
+            4,
+            6,
+            (0.0, 1.0),
+            (1.0, 2.0),
+        )));
+        assert_eq!(pmb.pcoord.system, CoordinateSystem::Cylindrical);
+    }
+
+    /// Smooth shallow-water state at (x1, x2)
+    fn initial_state(x1: Real, x2: Real) -> [Real; 3] {
+        [
+            1.0 + 0.2 * (3.0 * x1).sin() * (2.0 * x2).cos(),
+            0.1 * (2.0 * x2).sin(),
+            -0.1 * x1,
+        ]
+    }
+
+    fn initialize(pmb: &mut MeshBlock) {
+        let ng = NGHOST as i32;
+        for j in -ng..pmb.nx2 as i32 + ng {
+            for i in -ng..pmb.nx1 as i32 + ng {
+                let x1 = pmb.pcoord.x1v[(i + ng) as usize];
+                let x2 = pmb.pcoord.x2v[(j + ng) as usize];
+                for (n, w) in initial_state(x1, x2).iter().enumerate() {
+                    *pmb.peos.w.set(n, j, i) = *w;
+                }
+            }
+        }
+        pmb.peos.primitive_to_conserved();
+    }
+
+    /// A 2 x 3 layout of MeshBlocks reproduces a single MeshBlock covering
+    /// the same domain
+    #[test]
+    fn mesh_step_matches_single_block() {
+        let (nx2, nx1) = (4, 5);
+        let system = CoordinateSystem::Cartesian;
+        let dt = 0.01;
+
+        let (x2lim, x1lim) = ((0.0, 1.0), (0.0, 1.5));
+
+        let mut mesh: Mesh = Mesh::new(2, 3, nx2, nx1, system, x2lim, x1lim);
+        let mut single: Mesh =
+            Mesh::new(1, 1, 2 * nx2, 3 * nx1, system, x2lim, x1lim);
+
+        let blocks = mesh.meshblock.iter_mut();
+        for pmb in blocks.chain(single.meshblock.iter_mut()) {
+            initialize(pmb);
+        }
+
+        for _ in 0..2 {
+            mesh.step(dt);
+            single.step(dt);
+        }
+
+        let ref_block = &single.meshblock[0];
+        for (b, pmb) in mesh.meshblock.iter().enumerate() {
+            let (jb, ib) = ((b / 3) as i32, (b % 3) as i32);
+            for n in 0..3 {
+                for j in 0..nx2 as i32 {
+                    for i in 0..nx1 as i32 {
+                        let expected = ref_block.peos.w.get(
+                            n,
+                            jb * nx2 as i32 + j,
+                            ib * nx1 as i32 + i,
+                        );
+                        approx::assert_abs_diff_eq!(
+                            pmb.peos.w.get(n, j, i),
+                            expected,
+                            epsilon = 1e-13
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// Ideal gas cooling to a unit pressure in its saturation adjustment
+    #[derive(Clone, Default)]
+    struct Cooling(IdealGas);
+
+    impl eos_trait::EquationOfState for Cooling {
+        unsafe fn primitive_to_conserved(
+            &self,
+            u: *mut Real,
+            w: &[Real],
+            comps: &[usize; NHYDRO],
+        ) {
+            unsafe { self.0.primitive_to_conserved(u, w, comps) }
+        }
+
+        unsafe fn conserved_to_primitive(
+            &self,
+            w: *mut Real,
+            u: &[Real],
+            comps: &[usize; NHYDRO],
+        ) {
+            unsafe { self.0.conserved_to_primitive(w, u, comps) }
+        }
+
+        fn pressure(&self, w: &[Real], comps: &[usize; NHYDRO]) -> Real {
+            self.0.pressure(w, comps)
+        }
+
+        fn sound_speed(&self, w: &[Real], comps: &[usize; NHYDRO]) -> Real {
+            self.0.sound_speed(w, comps)
+        }
+
+        fn energy(&self, w: &[Real], comps: &[usize; NHYDRO]) -> Real {
+            self.0.energy(w, comps)
+        }
+
+        fn saturation_adjustment(
+            &self,
+            w: &mut [Real],
+            comps: &[usize; NHYDRO],
+        ) -> bool {
+            w[comps[IPR]] = 1.0;
+            true
+        }
+    }
+
+    /// The saturation adjustment runs at the end of each step
+    #[test]
+    fn mesh_step_saturation_adjustment() {
+        let system = CoordinateSystem::Cartesian;
+        let (x2lim, x1lim) = ((0.0, 1.0), (0.0, 1.0));
+        let mut mesh: Mesh<Cooling> =
+            Mesh::new(2, 2, 4, 4, system, x2lim, x1lim);
+
+        for pmb in mesh.meshblock.iter_mut() {
+            pmb.peos.w.data.fill(0.0);
+            for w in pmb.peos.w.all_mut() {
+                unsafe {
+                    *w.add(pmb.peos.comps[IDN]) = 1.0;
+                    *w.add(pmb.peos.comps[IPR]) = 2.0;
+                }
+            }
+            pmb.peos.primitive_to_conserved();
+        }
+
+        mesh.step(0.01);
+
+        for pmb in mesh.meshblock.iter() {
+            let comps = pmb.peos.comps;
+            for (w, u) in pmb.peos.w.interior().zip(pmb.peos.u.interior()) {
+                assert_eq!(w[comps[IPR]], 1.0);
+                approx::assert_abs_diff_eq!(
+                    u[comps[IPR]],
+                    2.5,
+                    epsilon = 1e-14
+                );
+            }
+        }
+    }
+
+    /// A uniform supersaturated flow condenses into clouds in Mesh::step,
+    /// conserving the dry air, the water, the momentum and the energy
+    #[cfg(feature = "moist")]
+    #[test]
+    fn mesh_step_condensation() {
+        use crate::eos::ideal_moist::IdealMoist;
+        use crate::thermo::{
+            saturation::SaturationCurve, thermodynamics::Thermodynamics,
+        };
+
+        // water vapor 1 condensing into cloud 2
+        let mut thermo =
+            Thermodynamics::new(&[1.46, 1.46], &[0.622, Real::INFINITY]);
+        thermo.saturation(1, 2, SaturationCurve::water());
+        let moist = IdealMoist::new(1.4, thermo);
+
+        // supersaturated at 300 K
+        let (rho, qv) = (1.2, 0.03);
+        let rho_v = [rho * qv, 0.0];
+        let pres = moist.thermo.pressure(300.0, rho * (1.0 - qv), &rho_v);
+
+        let system = CoordinateSystem::Cartesian;
+        let (x2lim, x1lim) = ((0.0, 1.0), (0.0, 1.0));
+        let mut mesh: Mesh<IdealMoist> =
+            Mesh::new(2, 2, 4, 4, system, x2lim, x1lim);
+        mesh.eos(moist.clone())
+            .boundary(BoundaryFace::InnerX1, BoundaryCondition::Periodic)
+            .boundary(BoundaryFace::InnerX2, BoundaryCondition::Periodic);
+
+        for pmb in mesh.meshblock.iter_mut() {
+            let comps = pmb.peos.comps;
+            pmb.peos.w.data.fill(0.0);
+            for w in pmb.peos.w.all_mut() {
+                unsafe {
+                    *w.add(comps[IDN]) = rho;
+                    *w.add(comps[1]) = qv;
+                    *w.add(comps[IVX]) = 3.0;
+                    *w.add(comps[IVY]) = -2.0;
+                    *w.add(comps[IPR]) = pres;
+                }
+            }
+            pmb.peos.primitive_to_conserved();
+        }
+
+        // dry air, water, momentum and energy
+        let total = |mesh: &Mesh<IdealMoist>| {
+            let mut total = [0.0; 4];
+            for pmb in mesh.meshblock.iter() {
+                let comps = pmb.peos.comps;
+                for u in pmb.peos.u.interior() {
+                    total[0] += u[comps[IDN]];
+                    total[1] += u[comps[1]] + u[comps[2]];
+                    total[2] += u[comps[IVX]];
+                    total[3] += u[comps[IPR]];
+                }
+            }
+            total
+        };
+        let total0 = total(&mesh);
+
+        let dt = mesh.new_dt(0.4);
+        mesh.step(dt);
+
+        for (t, t0) in total(&mesh).iter().zip(total0) {
+            approx::assert_relative_eq!(*t, t0, max_relative = 1e-14);
+        }
+
+        // the vapor is saturated at the raised temperature
+        let thermo = &moist.thermo;
+        let sat = SaturationCurve::water();
+        for pmb in mesh.meshblock.iter() {
+            let comps = pmb.peos.comps;
+            for w in pmb.peos.w.interior() {
+                let rho_v = [w[comps[1]], w[comps[2]]].map(|q| rho * q);
+                let rho_d = rho - rho_v[0] - rho_v[1];
+                let temp = thermo.temperature(w[comps[IPR]], rho_d, &rho_v);
+
+                assert!(rho_v[1] > 0.0 && temp > 300.0);
+                approx::assert_relative_eq!(
+                    rho_v[0] * thermo.gas_constant(1) * temp,
+                    sat.pressure(temp),
+                    max_relative = 1e-10
+                );
+            }
+        }
+    }
+
+    /// A non-periodic condition on a periodic face removes the pairing
+    #[test]
+    fn mesh_boundary_resets_periodic() {
+        let system = CoordinateSystem::Cartesian;
+        let (x2lim, x1lim) = ((0.0, 1.0), (0.0, 1.0));
+        let mut mesh: Mesh = Mesh::new(2, 2, 4, 4, system, x2lim, x1lim);
+        let nghost = mesh.ghost.len();
+
+        mesh.boundary(BoundaryFace::InnerX1, BoundaryCondition::Periodic);
+        assert!(mesh.ghost.len() > nghost);
+
+        mesh.boundary(BoundaryFace::OuterX1, BoundaryCondition::Reflecting);
+        assert_eq!(mesh.periodic, [false, false]);
+        assert_eq!(mesh.ghost.len(), nghost);
+        for (b, pmb) in mesh.meshblock.iter().enumerate() {
+            let (inner, outer) = (
+                pmb.bvals.get(BoundaryFace::InnerX1),
+                pmb.bvals.get(BoundaryFace::OuterX1),
+            );
+            if b % 2 == 0 {
+                assert!(matches!(inner, BoundaryCondition::Reflecting));
+                assert!(matches!(outer, BoundaryCondition::Block));
+            } else {
+                assert!(matches!(inner, BoundaryCondition::Block));
+                assert!(matches!(outer, BoundaryCondition::Reflecting));
+            }
+        }
+
+        mesh.boundary(BoundaryFace::InnerX1, BoundaryCondition::Outflow);
+        let inner = mesh.meshblock[0].bvals.get(BoundaryFace::InnerX1);
+        assert!(matches!(inner, BoundaryCondition::Outflow));
+        let outer = mesh.meshblock[1].bvals.get(BoundaryFace::OuterX1);
+        assert!(matches!(outer, BoundaryCondition::Reflecting));
+    }
+
+    /// Periodic boundaries of a 2 x 2 layout of MeshBlocks reproduce a
+    /// periodic single MeshBlock and conserve mass
+    #[test]
+    fn mesh_periodic_boundary() {
+        let (nx2, nx1) = (4, 4);
+        let system = CoordinateSystem::Cartesian;
+        let (x2lim, x1lim) = ((0.0, 1.0), (0.0, 1.0));
+
+        let mut mesh: Mesh = Mesh::new(2, 2, nx2, nx1, system, x2lim, x1lim);
+        let mut single: Mesh =
+            Mesh::new(1, 1, 2 * nx2, 2 * nx1, system, x2lim, x1lim);
+
+        for m in [&mut mesh, &mut single] {
+            m.boundary(BoundaryFace::InnerX1, BoundaryCondition::Periodic)
+                .boundary(BoundaryFace::OuterX2, BoundaryCondition::Periodic);
+            for pmb in m.meshblock.iter_mut() {
+                initialize(pmb);
+            }
+        }
+
+        let mass = |mesh: &Mesh| -> Real {
+            mesh.meshblock
+                .iter()
+                .flat_map(|pmb| pmb.peos.u.interior().map(|u| u[IDN]))
+                .sum()
+        };
+        let mass0 = mass(&mesh);
+
+        for _ in 0..2 {
+            mesh.step(0.01);
+            single.step(0.01);
+        }
+
+        approx::assert_abs_diff_eq!(mass(&mesh), mass0, epsilon = 1e-12);
+
+        let ref_block = &single.meshblock[0];
+        for (b, pmb) in mesh.meshblock.iter().enumerate() {
+            let (jb, ib) = ((b / 2) as i32, (b % 2) as i32);
+            for j in 0..nx2 as i32 {
+                for i in 0..nx1 as i32 {
+                    approx::assert_abs_diff_eq!(
+                        pmb.peos.w.get(0, j, i),
+                        ref_block.peos.w.get(
+                            0,
+                            jb * nx2 as i32 + j,
+                            ib * nx1 as i32 + i
+                        ),
+                        epsilon = 1e-13
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn mesh_new_dt() {
+        let system = CoordinateSystem::Cartesian;
+        let mut mesh: Mesh =
+            Mesh::new(1, 2, 4, 4, system, (0.0, 1.0), (0.0, 2.0));
+
+        for pmb in mesh.meshblock.iter_mut() {
+            pmb.peos.w.data.fill(1.0);
+        }
+        *mesh.meshblock[1].peos.w.set(0, 2, 3) = 4.0;
+
+        let dt = mesh.new_dt(0.5);
+        approx::assert_abs_diff_eq!(dt, mesh.meshblock[1].new_dt(0.5));
+        assert!(dt < mesh.meshblock[0].new_dt(0.5));
+    }
+
+    /// Riemann problem solved by Hydro across two MeshBlocks compared with
+    /// the exact solution
+    fn riemann_error<E: ExactRiemann>(
+        mesh: &mut Mesh<E>,
+        wl: [Real; NHYDRO],
+        wr: [Real; NHYDRO],
+        tmax: Real,
+    ) -> Real {
+        let ng = NGHOST as i32;
+        for pmb in mesh.meshblock.iter_mut() {
+            for j in -ng..pmb.nx2 as i32 + ng {
+                for i in -ng..pmb.nx1 as i32 + ng {
+                    let x1 = pmb.pcoord.x1v[(i + ng) as usize];
+                    let w = if x1 < 0.5 { wl } else { wr };
+                    for (n, w) in w.iter().enumerate() {
+                        *pmb.peos.w.set(n, j, i) = *w;
+                    }
+                }
+            }
+            pmb.peos.primitive_to_conserved();
+        }
+
+        let mut time = 0.0;
+        while time < tmax {
+            let dt = mesh.new_dt(0.4).min(tmax - time);
+            mesh.step(dt);
+            time += dt;
+        }
+
+        // mean over the cells of the first row
+        let (mut error, mut ncell) = (0.0, 0);
+        for pmb in mesh.meshblock.iter() {
+            for i in 0..pmb.nx1 as i32 {
+                let x1 = pmb.pcoord.x1v[(i + ng) as usize];
+                let w = pmb.peos.law.sample(&wl, &wr, IVX, (x1 - 0.5) / tmax);
+                error += (pmb.peos.w.get(0, 0, i) - w[IDN]).abs();
+                ncell += 1;
+            }
+        }
+
+        error / ncell as Real
+    }
+
+    /// Sod shock tube
+    fn sod_error(recon: Reconstruction, characteristic: bool) -> Real {
+        let system = CoordinateSystem::Cartesian;
+        let mut mesh: Mesh<IdealGas> =
+            Mesh::new(1, 2, 2, 100, system, (0.0, 1.0), (0.0, 1.0));
+        mesh.riemann_solver(RiemannSolver::Hllc)
+            .reconstruction(recon)
+            .characteristic(characteristic);
+
+        let wl = dry_state([1.0, 0.0, 0.0, 0.0, 1.0]);
+        let wr = dry_state([0.125, 0.0, 0.0, 0.0, 0.1]);
+        riemann_error(&mut mesh, wl, wr, 0.2)
+    }
+
+    #[test]
+    fn mesh_sod_matches_exact() {
+        use crate::reconstruct::plm::Limiter;
+
+        for recon in [
+            Reconstruction::Weno5,
+            Reconstruction::WenoZ,
+            Reconstruction::Plm(Limiter::MonotonizedCentral),
+            Reconstruction::Ppm {
+                steepen: false,
+                flatten: false,
+            },
+            Reconstruction::Ppm {
+                steepen: true,
+                flatten: true,
+            },
+        ] {
+            let error = sod_error(recon, false);
+            assert!(error < 0.01, "{:?}: L1 density error = {}", recon, error);
+        }
+    }
+
+    /// Characteristic reconstruction reduces the oscillations of WENO5
+    /// behind the strong shock of the Lax problem
+    #[test]
+    fn mesh_characteristic_lax() {
+        let wl = dry_state([0.445, 0.698, 0.0, 0.0, 3.528]);
+        let wr = dry_state([0.5, 0.0, 0.0, 0.0, 0.571]);
+
+        let variation = [false, true].map(|characteristic| {
+            let system = CoordinateSystem::Cartesian;
+            let mut mesh: Mesh<IdealGas> =
+                Mesh::new(1, 2, 2, 100, system, (0.0, 1.0), (0.0, 1.0));
+            mesh.riemann_solver(RiemannSolver::Hllc)
+                .reconstruction(Reconstruction::Weno5)
+                .characteristic(characteristic);
+
+            let error = riemann_error(&mut mesh, wl, wr, 0.13);
+            assert!(error < 0.02, "L1 density error = {}", error);
+
+            let rho: Vec<Real> = mesh
+                .meshblock
+                .iter()
+                .flat_map(|pmb| {
+                    (0..pmb.nx1 as i32).map(|i| pmb.peos.w.get(0, 0, i))
+                })
+                .collect();
+            rho.windows(2).map(|rho| (rho[1] - rho[0]).abs()).sum::<Real>()
+        });
+
+        assert!(variation[1] < variation[0], "{:?}", variation);
+    }
+
+    /// Shallow-water dam break reconstructed in characteristic variables
+    #[test]
+    fn mesh_characteristic_dam_break() {
+        use crate::eos::shallow_water::ShallowWater;
+
+        let system = CoordinateSystem::Cartesian;
+        let mut mesh: Mesh<ShallowWater> =
+            Mesh::new(1, 2, 2, 100, system, (0.0, 1.0), (0.0, 1.0));
+        mesh.riemann_solver(RiemannSolver::RoeShallowWater)
+            .reconstruction(Reconstruction::Weno5)
+            .characteristic(true);
+
+        let wl = dry_state([2.0, 0.0, 0.0, 0.0, 2.0]);
+        let wr = dry_state([1.0, 0.0, 0.0, 0.0, 0.5]);
+        let error = riemann_error(&mut mesh, wl, wr, 0.2);
+        assert!(error < 0.01, "L1 height error = {}", error);
+    }
 }
@@ -0,0 +1,3 @@
+pub mod cubed_sphere;
+#[allow(clippy::module_inception)]
+pub mod mesh;
@@ -1,3 +1,9 @@
+pub mod plm;
 pub mod poly;
+pub mod ppm;
+pub mod reconstruction;
+pub mod teno5;
 pub mod weno3;
 pub mod weno5;
+pub mod wenom;
+pub mod wenoz;
@@ -0,0 +1,95 @@
+//! Piecewise linear (PLM) interpolation
+//! Reference: van Leer (1979), Sweby (1984)
+
+use std::str::FromStr;
+
+/// Slope limiters, in order of increasing compressiveness
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Limiter {
+    Minmod,
+    VanLeer,
+    #[default]
+    MonotonizedCentral,
+    Superbee,
+}
+
+impl Limiter {
+    /// Limited slope of a cell from its left and right differences
+    pub fn slope(&self, dl: f64, dr: f64) -> f64 {
+        if dl * dr <= 0.0 {
+            return 0.0;
+        }
+
+        let sign = dl.signum();
+        let (dl, dr) = (dl.abs(), dr.abs());
+        sign * match self {
+            Limiter::Minmod => dl.min(dr),
+            Limiter::VanLeer => 2.0 * dl * dr / (dl + dr),
+            Limiter::MonotonizedCentral => {
+                (0.5 * (dl + dr)).min(2.0 * dl).min(2.0 * dr)
+            },
+            Limiter::Superbee => {
+                (2.0 * dl).min(dr).max(dl.min(2.0 * dr))
+            },
+        }
+    }
+}
+
+impl FromStr for Limiter {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "minmod" => Ok(Limiter::Minmod),
+            "vanleer" => Ok(Limiter::VanLeer),
+            "mc" => Ok(Limiter::MonotonizedCentral),
+            "superbee" => Ok(Limiter::Superbee),
+            _ => Err(format!("Unknown slope limiter: {}", name)),
+        }
+    }
+}
+
+/// | x_{-1} | x_0 | x_1 |
+///          ^
+///          |
+///          return value
+pub fn interp_plm(phim1: f64, phi: f64, phip1: f64, limiter: Limiter) -> f64 {
+    phi - 0.5 * limiter.slope(phi - phim1, phip1 - phi)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMITERS: [Limiter; 4] = [
+        Limiter::Minmod,
+        Limiter::VanLeer,
+        Limiter::MonotonizedCentral,
+        Limiter::Superbee,
+    ];
+
+    #[test]
+    fn test_interp_plm_linear() {
+        for limiter in LIMITERS {
+            let result = interp_plm(1.0, 2.0, 3.0, limiter);
+            approx::assert_abs_diff_eq!(result, 1.5, epsilon = f64::EPSILON);
+        }
+    }
+
+    #[test]
+    fn test_interp_plm_extremum() {
+        for limiter in LIMITERS {
+            assert_eq!(interp_plm(1.0, 2.0, 1.5, limiter), 2.0);
+        }
+    }
+
+    #[test]
+    fn test_slope() {
+        let (dl, dr) = (1.0, 3.0);
+        assert_eq!(Limiter::Minmod.slope(dl, dr), 1.0);
+        assert_eq!(Limiter::VanLeer.slope(dl, dr), 1.5);
+        assert_eq!(Limiter::MonotonizedCentral.slope(dl, dr), 2.0);
+        assert_eq!(Limiter::Superbee.slope(dl, dr), 2.0);
+        assert_eq!(Limiter::Superbee.slope(-1.0, -1.5), -1.5);
+    }
+}
@@ -0,0 +1,146 @@
+//! Piecewise parabolic (PPM) interpolation
+//! Reference: Colella & Woodward (1984)
+//!
+//! The face values start from the fourth-order interpolation interp_cp4,
+//! bounded by the adjacent cell averages. Contact steepening and flattening
+//! near shocks are optional and act on the face values before the
+//! monotonicity constraint of the parabola.
+
+use crate::reconstruct::{plm::Limiter, poly::interp_cp4};
+
+/// Steepening parameters (eta_1, eta_2, epsilon, K_0)
+const STEEPEN: (f64, f64, f64, f64) = (20.0, 0.05, 0.01, 0.1);
+
+/// Flattening parameters (epsilon, omega_1, omega_2)
+const FLATTEN: (f64, f64, f64) = (0.33, 0.75, 10.0);
+
+/// | x_{-2} | x_{-1} | x_0 | x_1 | x_2 |
+///                   ^     ^
+///                   |     |
+///                   return values
+pub fn interp_ppm(phi: &[f64; 5]) -> (f64, f64) {
+    let [phim2, phim1, phi, phip1, phip2] = *phi;
+
+    let left = interp_cp4(phim2, phim1, phi, phip1)
+        .clamp(phim1.min(phi), phim1.max(phi));
+    let right = interp_cp4(phim1, phi, phip1, phip2)
+        .clamp(phi.min(phip1), phi.max(phip1));
+
+    (left, right)
+}
+
+/// Constrain the parabola through the face values left and right with
+/// the cell average phi to be monotone
+pub fn limit_ppm(phi: f64, left: f64, right: f64) -> (f64, f64) {
+    if (right - phi) * (phi - left) <= 0.0 {
+        return (phi, phi);
+    }
+
+    let dphi = right - left;
+    let curv = dphi * (phi - 0.5 * (left + right));
+    if curv > dphi * dphi / 6.0 {
+        (3.0 * phi - 2.0 * right, right)
+    } else if -dphi * dphi / 6.0 > curv {
+        (left, 3.0 * phi - 2.0 * left)
+    } else {
+        (left, right)
+    }
+}
+
+/// Steepening coefficient of the density stencil rho at a contact. The
+/// pressure jump condition uses K_0 without the adiabatic index.
+pub fn steepening(rho: &[f64; 5], pres: &[f64; 5]) -> f64 {
+    let (eta1, eta2, eps, k0) = STEEPEN;
+    let [rhom2, rhom1, _, rhop1, rhop2] = *rho;
+    let d2m = rhom2 - 2.0 * rhom1 + rho[2];
+    let d2p = rho[2] - 2.0 * rhop1 + rhop2;
+    let drho = rhop1 - rhom1;
+
+    let dpres = (pres[3] - pres[1]).abs();
+    if d2m * d2p >= 0.0
+        || drho.abs() <= eps * rhop1.abs().min(rhom1.abs())
+        || k0 * dpres * rhop1.min(rhom1) > drho.abs() * pres[3].min(pres[1])
+    {
+        return 0.0;
+    }
+
+    let eta = -(d2p - d2m) / (6.0 * drho);
+    (eta1 * (eta - eta2)).clamp(0.0, 1.0)
+}
+
+/// Face values of the density stencil rho steepened toward the limited
+/// linear extrapolations of the neighboring cells
+pub fn steepened_faces(rho: &[f64; 5]) -> (f64, f64) {
+    let mc = Limiter::MonotonizedCentral;
+    let slopem1 = mc.slope(rho[1] - rho[0], rho[2] - rho[1]);
+    let slopep1 = mc.slope(rho[3] - rho[2], rho[4] - rho[3]);
+    (rho[1] + 0.5 * slopem1, rho[3] - 0.5 * slopep1)
+}
+
+/// Flattening coefficient of a cell in a shock, from the pressure stencil
+/// pres and the stencil vel of the velocity normal to the faces
+pub fn flattening(pres: &[f64; 5], vel: &[f64; 5]) -> f64 {
+    let (eps, omega1, omega2) = FLATTEN;
+    let dp1 = pres[3] - pres[1];
+    let dp2 = pres[4] - pres[0];
+
+    if dp1.abs() <= eps * pres[3].min(pres[1])
+        || vel[1] - vel[3] <= 0.0
+        || dp2 == 0.0
+    {
+        return 0.0;
+    }
+
+    ((dp1 / dp2 - omega1) * omega2).clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interp_ppm_linear() {
+        let (left, right) = interp_ppm(&[1.0, 2.0, 3.0, 4.0, 5.0]);
+        approx::assert_abs_diff_eq!(left, 2.5, epsilon = f64::EPSILON);
+        approx::assert_abs_diff_eq!(right, 3.5, epsilon = f64::EPSILON);
+        assert_eq!(limit_ppm(3.0, left, right), (left, right));
+    }
+
+    #[test]
+    fn test_limit_ppm() {
+        // local extremum
+        assert_eq!(limit_ppm(2.0, 1.5, 1.8), (2.0, 2.0));
+
+        // the parabola overshoots on the right
+        let (left, right) = limit_ppm(1.0, 0.9, 2.0);
+        approx::assert_abs_diff_eq!(left, 0.9);
+        approx::assert_abs_diff_eq!(right, 1.2, epsilon = 1e-15);
+    }
+
+    #[test]
+    fn test_steepening() {
+        let rho = [1.0, 1.0, 0.55, 0.1, 0.1];
+        let pres = [1.0; 5];
+        assert!(steepening(&rho, &pres) > 0.0);
+        assert_eq!(steepened_faces(&rho), (1.0, 0.1));
+
+        // not a contact when the pressure jumps as well
+        let pres = [100.0, 100.0, 50.0, 1.0, 1.0];
+        assert_eq!(steepening(&rho, &pres), 0.0);
+    }
+
+    #[test]
+    fn test_flattening() {
+        let pres = [10.0, 10.0, 5.0, 1.0, 1.0];
+        let vel = [1.0, 1.0, 0.5, 0.0, 0.0];
+        assert_eq!(flattening(&pres, &vel), 1.0);
+
+        // expansion
+        let vel = [0.0, 0.0, 0.5, 1.0, 1.0];
+        assert_eq!(flattening(&pres, &vel), 0.0);
+
+        // smooth
+        let pres = [1.0, 1.01, 1.02, 1.03, 1.04];
+        assert_eq!(flattening(&pres, &[1.0, 1.0, 0.5, 0.0, 0.0]), 0.0);
+    }
+}
@@ -0,0 +1,348 @@
+//! Reconstruction selection
+//!
+//! Reconstruction interpolates the cell averages of a five-cell stencil to
+//! the two faces of its center cell. The scheme is chosen at run time,
+//! e.g. parsed from a configuration string.
+//!
+//! PPM contact steepening and flattening couple the components of a cell
+//! to its pressure, which the caller computes from the equation of state.
+//! They are only applied by cell_faces; characteristic_faces rejects them.
+
+use std::str::FromStr;
+use crate::eos::eos_trait::Matrix;
+use crate::reconstruct::{
+    plm::{interp_plm, Limiter},
+    ppm::{
+        flattening, interp_ppm, limit_ppm, steepened_faces, steepening,
+    },
+    teno5::interp_teno5,
+    weno3::interp_weno3,
+    weno5::interp_weno5,
+    wenom::interp_wenom,
+    wenoz::interp_wenoz,
+};
+use crate::utils::defs::{Real, IDN, NHYDRO};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Reconstruction {
+    // piecewise constant
+    Donor,
+    Plm(Limiter),
+    // optional contact steepening and flattening at shocks
+    Ppm { steepen: bool, flatten: bool },
+    Weno3,
+    #[default]
+    Weno5,
+    WenoZ,
+    WenoM,
+    Teno5,
+}
+
+impl Reconstruction {
+    /// Values at the left and right faces of the center cell of the stencil
+    /// | w_{-2} | w_{-1} |* w_{0} *| w_{1} | w_{2} |
+    pub fn faces(&self, w: &[Real; 5]) -> (Real, Real) {
+        let [wm2, wm1, w, wp1, wp2] = *w;
+
+        match self {
+            Reconstruction::Donor => (w, w),
+            Reconstruction::Plm(limiter) => (
+                interp_plm(wm1, w, wp1, *limiter),
+                interp_plm(wp1, w, wm1, *limiter),
+            ),
+            Reconstruction::Ppm { .. } => {
+                let (left, right) = interp_ppm(&[wm2, wm1, w, wp1, wp2]);
+                limit_ppm(w, left, right)
+            },
+            Reconstruction::Weno3 => {
+                (interp_weno3(wm1, w, wp1), interp_weno3(wp1, w, wm1))
+            },
+            Reconstruction::Weno5 => (
+                interp_weno5(wm2, wm1, w, wp1, wp2),
+                interp_weno5(wp2, wp1, w, wm1, wm2),
+            ),
+            Reconstruction::WenoZ => (
+                interp_wenoz(wm2, wm1, w, wp1, wp2),
+                interp_wenoz(wp2, wp1, w, wm1, wm2),
+            ),
+            Reconstruction::WenoM => (
+                interp_wenom(wm2, wm1, w, wp1, wp2),
+                interp_wenom(wp2, wp1, w, wm1, wm2),
+            ),
+            Reconstruction::Teno5 => (
+                interp_teno5(wm2, wm1, w, wp1, wp2),
+                interp_teno5(wp2, wp1, w, wm1, wm2),
+            ),
+        }
+    }
+
+    /// Values at the left and right faces of the center cell for all
+    /// components of the stencil w, where ivx is the velocity normal to
+    /// the faces. The pressure stencil pres is only evaluated for PPM
+    /// steepening or flattening.
+    pub fn cell_faces(
+        &self,
+        w: &[[Real; 5]; NHYDRO],
+        ivx: usize,
+        pres: impl FnOnce() -> [Real; 5],
+    ) -> [(Real, Real); NHYDRO] {
+        let (steepen, flatten) = match *self {
+            Reconstruction::Ppm { steepen, flatten } if steepen || flatten => {
+                (steepen, flatten)
+            },
+            _ => return w.map(|w| self.faces(&w)),
+        };
+        let pres = pres();
+
+        let flat = if flatten {
+            flattening(&pres, &w[ivx])
+        } else {
+            0.0
+        };
+
+        std::array::from_fn(|n| {
+            let (mut left, mut right) = interp_ppm(&w[n]);
+
+            if steepen && n == IDN {
+                let eta = steepening(&w[IDN], &pres);
+                let (sleft, sright) = steepened_faces(&w[IDN]);
+                left = (1.0 - eta) * left + eta * sleft;
+                right = (1.0 - eta) * right + eta * sright;
+            }
+
+            left = flat * w[n][2] + (1.0 - flat) * left;
+            right = flat * w[n][2] + (1.0 - flat) * right;
+
+            limit_ppm(w[n][2], left, right)
+        })
+    }
+
+    /// Values at the left and right faces of the center cell for all
+    /// components of the stencil w, reconstructed in the characteristic
+    /// variables of the left eigenvectors and projected back by the right
+    /// eigenvectors
+    ///
+    /// # Panics
+    ///
+    /// Panics for PPM with contact steepening or flattening, which have no
+    /// characteristic counterpart.
+    pub fn characteristic_faces(
+        &self,
+        w: &[[Real; 5]; NHYDRO],
+        left: &Matrix,
+        right: &Matrix,
+    ) -> [(Real, Real); NHYDRO] {
+        assert!(
+            !matches!(self, Reconstruction::Ppm { steepen, flatten }
+                if *steepen || *flatten),
+            "PPM steepening and flattening need primitive variables"
+        );
+        let q: [(Real, Real); NHYDRO] = std::array::from_fn(|k| {
+            let stencil = std::array::from_fn(|s| {
+                (0..NHYDRO).map(|n| left[k][n] * w[n][s]).sum()
+            });
+            self.faces(&stencil)
+        });
+
+        std::array::from_fn(|n| {
+            q.iter().zip(right[n]).fold((0.0, 0.0), |(ql, qr), (q, r)| {
+                (ql + r * q.0, qr + r * q.1)
+            })
+        })
+    }
+}
+
+impl FromStr for Reconstruction {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.split_once('-') {
+            Some(("plm", limiter)) => Ok(Reconstruction::Plm(limiter.parse()?)),
+            Some(("ppm", options)) => {
+                let (mut steepen, mut flatten) = (false, false);
+                for option in options.split('-') {
+                    match option {
+                        "steep" => steepen = true,
+                        "flat" => flatten = true,
+                        _ => {
+                            return Err(format!(
+                                "Unknown PPM option: {}",
+                                option
+                            ))
+                        },
+                    }
+                }
+                Ok(Reconstruction::Ppm { steepen, flatten })
+            },
+            _ => match name {
+                "donor" => Ok(Reconstruction::Donor),
+                "plm" => Ok(Reconstruction::Plm(Limiter::default())),
+                "ppm" => Ok(Reconstruction::Ppm {
+                    steepen: false,
+                    flatten: false,
+                }),
+                "weno3" => Ok(Reconstruction::Weno3),
+                "weno5" => Ok(Reconstruction::Weno5),
+                "wenoz" => Ok(Reconstruction::WenoZ),
+                "wenom" => Ok(Reconstruction::WenoM),
+                "teno5" => Ok(Reconstruction::Teno5),
+                _ => Err(format!("Unknown reconstruction: {}", name)),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::defs::{IPR, IVX};
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!(
+            "plm-minmod".parse::<Reconstruction>(),
+            Ok(Reconstruction::Plm(Limiter::Minmod))
+        );
+        assert_eq!(
+            "plm".parse::<Reconstruction>(),
+            Ok(Reconstruction::Plm(Limiter::MonotonizedCentral))
+        );
+        assert_eq!(
+            "ppm-steep-flat".parse(),
+            Ok(Reconstruction::Ppm {
+                steepen: true,
+                flatten: true,
+            })
+        );
+        assert_eq!("weno5".parse(), Ok(Reconstruction::Weno5));
+        assert_eq!("wenoz".parse(), Ok(Reconstruction::WenoZ));
+        assert_eq!("teno5".parse(), Ok(Reconstruction::Teno5));
+        assert!("ppm-smooth".parse::<Reconstruction>().is_err());
+        assert!("plm-none".parse::<Reconstruction>().is_err());
+        assert!("weno7".parse::<Reconstruction>().is_err());
+    }
+
+    /// All schemes reproduce linear data
+    #[test]
+    fn test_faces_linear() {
+        for recon in [
+            Reconstruction::Plm(Limiter::Minmod),
+            Reconstruction::Plm(Limiter::Superbee),
+            Reconstruction::Ppm {
+                steepen: false,
+                flatten: false,
+            },
+            Reconstruction::Weno3,
+            Reconstruction::Weno5,
+            Reconstruction::WenoZ,
+            Reconstruction::WenoM,
+            Reconstruction::Teno5,
+        ] {
+            let (left, right) = recon.faces(&[1.0, 2.0, 3.0, 4.0, 5.0]);
+            approx::assert_abs_diff_eq!(left, 2.5, epsilon = 1e-12);
+            approx::assert_abs_diff_eq!(right, 3.5, epsilon = 1e-12);
+        }
+    }
+
+    /// The WENO5 variants are closer to the linear scheme than the classic
+    /// weights at a smooth extremum
+    #[test]
+    fn test_faces_critical_point() {
+        let dx = 0.2;
+        let x0 = std::f64::consts::FRAC_PI_2 + 0.3 * dx;
+        let stencil: [Real; 5] = std::array::from_fn(|k| {
+            let x = x0 + (k as Real - 2.0) * dx;
+            ((x - 0.5 * dx).cos() - (x + 0.5 * dx).cos()) / dx
+        });
+        let error = |recon: Reconstruction| {
+            let (left, right) = recon.faces(&stencil);
+            (left - (x0 - 0.5 * dx).sin()).abs()
+                + (right - (x0 + 0.5 * dx).sin()).abs()
+        };
+
+        let weno5 = error(Reconstruction::Weno5);
+        for recon in [
+            Reconstruction::WenoZ,
+            Reconstruction::WenoM,
+            Reconstruction::Teno5,
+        ] {
+            assert!(error(recon) < weno5, "{:?}", recon);
+        }
+    }
+
+    /// Characteristic faces reduce to the component faces on data the
+    /// scheme reproduces exactly
+    #[test]
+    fn test_characteristic_faces() {
+        use crate::eos::{eos_trait::EquationOfState, ideal_gas::IdealGas};
+
+        let mut w = [[0.0; 5]; NHYDRO];
+        w[IDN] = [1.0, 1.2, 0.9, 1.1, 1.3];
+        w[IVX] = [0.1, -0.2, 0.3, 0.0, 0.2];
+        w[IPR] = [1.0, 0.8, 1.1, 1.2, 0.9];
+
+        let comps = std::array::from_fn(|n| n);
+        let center = w.map(|w| w[2]);
+        let eos = IdealGas::default();
+        let (left, right) = eos.eigenvectors(&center, &comps, IVX);
+
+        let donor = Reconstruction::Donor;
+        let faces = donor.characteristic_faces(&w, &left, &right);
+        for (face, w) in faces.iter().zip(center) {
+            approx::assert_abs_diff_eq!(face.0, w, epsilon = 1e-14);
+            approx::assert_abs_diff_eq!(face.1, w, epsilon = 1e-14);
+        }
+
+        // linear data
+        for w in w.iter_mut() {
+            *w = std::array::from_fn(|s| w[2] + 0.1 * (s as Real - 2.0));
+        }
+        let recon = Reconstruction::Weno5;
+        let faces = recon.characteristic_faces(&w, &left, &right);
+        let expected = recon.cell_faces(&w, IVX, || w[IPR]);
+        for (face, expected) in faces.iter().zip(expected) {
+            approx::assert_abs_diff_eq!(face.0, expected.0, epsilon = 1e-12);
+            approx::assert_abs_diff_eq!(face.1, expected.1, epsilon = 1e-12);
+        }
+    }
+
+    /// Flattening at a shock drops PPM to first order
+    #[test]
+    fn test_cell_faces_flatten() {
+        let mut w = [[1.0; 5]; NHYDRO];
+        w[IDN] = [4.0, 4.0, 2.5, 1.0, 1.0];
+        w[IPR] = [10.0, 10.0, 5.0, 1.0, 1.0];
+        w[IVX] = [1.0, 1.0, 0.5, 0.0, 0.0];
+
+        let recon = Reconstruction::Ppm {
+            steepen: true,
+            flatten: true,
+        };
+        let faces = recon.cell_faces(&w, IVX, || w[IPR]);
+        assert_eq!(faces[IDN], (2.5, 2.5));
+        assert_eq!(faces[IPR], (5.0, 5.0));
+
+        let recon = Reconstruction::Ppm {
+            steepen: false,
+            flatten: false,
+        };
+        let faces = recon.cell_faces(&w, IVX, || w[IPR]);
+        assert_eq!(faces[IDN], recon.faces(&w[IDN]));
+        assert!(faces[IDN].0 > faces[IDN].1);
+    }
+
+    /// Steepening and flattening are not applied to characteristic fields
+    #[test]
+    #[should_panic(expected = "PPM steepening and flattening")]
+    fn test_characteristic_faces_flatten() {
+        let w = [[1.0; 5]; NHYDRO];
+        let identity = std::array::from_fn(|k| {
+            std::array::from_fn(|n| if k == n { 1.0 } else { 0.0 })
+        });
+        let recon = Reconstruction::Ppm {
+            steepen: false,
+            flatten: true,
+        };
+        recon.characteristic_faces(&w, &identity, &identity);
+    }
+}
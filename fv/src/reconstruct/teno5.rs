@@ -0,0 +1,54 @@
+//! TENO5 interpolation
+//! Reference: Fu et al. (2016), J. Comput. Phys. 305, 333-359
+//!
+//! Candidate stencils crossing a discontinuity are discarded by a sharp
+//! cut-off, the others keep their linear weights.
+
+use crate::reconstruct::weno5::{weno5_stencils, WENO5_WEIGHTS};
+
+/// Cut-off of the normalized smoothness measure
+const CUT_OFF: f64 = 1e-5;
+
+/// | x_{-2} | x_{-1} | x_0 | x_1 | x_2 |
+///                   ^
+///                   |
+///                   return value
+pub fn interp_teno5(
+    phim2: f64,
+    phim1: f64,
+    phi: f64,
+    phip1: f64,
+    phip2: f64,
+) -> f64 {
+    let (p, beta) = weno5_stencils(phim2, phim1, phi, phip1, phip2);
+
+    let tau5 = (beta[0] - beta[2]).abs();
+    let gamma: [f64; 3] =
+        std::array::from_fn(|k| (1.0 + tau5 / (beta[k] + 1e-40)).powi(6));
+    let sum = gamma[0] + gamma[1] + gamma[2];
+
+    let alpha: [f64; 3] = std::array::from_fn(|k| {
+        if gamma[k] / sum < CUT_OFF {
+            0.0
+        } else {
+            WENO5_WEIGHTS[k]
+        }
+    });
+
+    (alpha[0] * p[0] + alpha[1] * p[1] + alpha[2] * p[2])
+        / (alpha[0] + alpha[1] + alpha[2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interp_teno5() {
+        let result = interp_teno5(1.0, 2.0, 3.0, 4.0, 5.0);
+        approx::assert_abs_diff_eq!(result, 2.5, epsilon = 1e-15);
+
+        // the stencils crossing the step are discarded
+        assert_eq!(interp_teno5(1.0, 1.0, 1.0, 0.0, 0.0), 1.0);
+    }
+}
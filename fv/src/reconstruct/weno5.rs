@@ -2,17 +2,18 @@
 //! Reference: https://en.wikipedia.org/wiki/WENO
 //! \todo use precomputed coefficients
 
-/// | x_{-2} | x_{-1} | x_0 | x_1 | x_2 |
-///                   ^
-///                   |
-///                   return value
-pub fn interp_weno5(
+/// Linear weights of the candidate stencils
+pub const WENO5_WEIGHTS: [f64; 3] = [0.3, 0.6, 0.1];
+
+/// Third-order candidate values and smoothness indicators of the three
+/// sub-stencils, shared by the WENO5 family
+pub fn weno5_stencils(
     phim2: f64,
     phim1: f64,
     phi: f64,
     phip1: f64,
     phip2: f64,
-) -> f64 {
+) -> ([f64; 3], [f64; 3]) {
     let p0 = (1.0 / 3.0) * phi + (5.0 / 6.0) * phim1 - (1.0 / 6.0) * phim2;
     let p1 = (-1.0 / 6.0) * phip1 + (5.0 / 6.0) * phi + (1.0 / 3.0) * phim1;
     let p2 = (1.0 / 3.0) * phip2 - (7.0 / 6.0) * phip1 + (11.0 / 6.0) * phi;
@@ -24,6 +25,23 @@ pub fn interp_weno5(
     let beta2 = 13.0 / 12.0 * (phip2 - 2.0 * phip1 + phi).powi(2)
         + 0.25 * (phip2 - 4.0 * phip1 + 3.0 * phi).powi(2);
 
+    ([p0, p1, p2], [beta0, beta1, beta2])
+}
+
+/// | x_{-2} | x_{-1} | x_0 | x_1 | x_2 |
+///                   ^
+///                   |
+///                   return value
+pub fn interp_weno5(
+    phim2: f64,
+    phim1: f64,
+    phi: f64,
+    phip1: f64,
+    phip2: f64,
+) -> f64 {
+    let ([p0, p1, p2], [beta0, beta1, beta2]) =
+        weno5_stencils(phim2, phim1, phi, phip1, phip2);
+
     let alpha0 = 0.3 / (beta0 + 1e-10).powi(2);
     let alpha1 = 0.6 / (beta1 + 1e-10).powi(2);
     let alpha2 = 0.1 / (beta2 + 1e-10).powi(2);
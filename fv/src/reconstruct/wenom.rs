@@ -0,0 +1,50 @@
+//! Mapped WENO (WENO-M) interpolation
+//! Reference: Henrick et al. (2005), J. Comput. Phys. 207, 542-567
+
+use crate::reconstruct::weno5::{weno5_stencils, WENO5_WEIGHTS};
+
+/// | x_{-2} | x_{-1} | x_0 | x_1 | x_2 |
+///                   ^
+///                   |
+///                   return value
+pub fn interp_wenom(
+    phim2: f64,
+    phim1: f64,
+    phi: f64,
+    phip1: f64,
+    phip2: f64,
+) -> f64 {
+    let (p, beta) = weno5_stencils(phim2, phim1, phi, phip1, phip2);
+
+    // classic Jiang-Shu weights
+    let alpha: [f64; 3] = std::array::from_fn(|k| {
+        WENO5_WEIGHTS[k] / (beta[k] + 1e-40).powi(2)
+    });
+    let sum = alpha[0] + alpha[1] + alpha[2];
+
+    // mapped toward the linear weights
+    let alpha: [f64; 3] = std::array::from_fn(|k| {
+        let d = WENO5_WEIGHTS[k];
+        let omega = alpha[k] / sum;
+        omega * (d + d * d - 3.0 * d * omega + omega * omega)
+            / (d * d + omega * (1.0 - 2.0 * d))
+    });
+
+    (alpha[0] * p[0] + alpha[1] * p[1] + alpha[2] * p[2])
+        / (alpha[0] + alpha[1] + alpha[2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interp_wenom() {
+        let result = interp_wenom(1.0, 2.0, 3.0, 4.0, 5.0);
+        approx::assert_abs_diff_eq!(result, 2.5, epsilon = 1e-15);
+
+        // a step is taken from the smooth side
+        let result = interp_wenom(1.0, 1.0, 1.0, 0.0, 0.0);
+        approx::assert_abs_diff_eq!(result, 1.0, epsilon = 1e-10);
+    }
+}
@@ -0,0 +1,42 @@
+//! WENO-Z interpolation
+//! Reference: Borges et al. (2008), J. Comput. Phys. 227, 3191-3211
+
+use crate::reconstruct::weno5::{weno5_stencils, WENO5_WEIGHTS};
+
+/// | x_{-2} | x_{-1} | x_0 | x_1 | x_2 |
+///                   ^
+///                   |
+///                   return value
+pub fn interp_wenoz(
+    phim2: f64,
+    phim1: f64,
+    phi: f64,
+    phip1: f64,
+    phip2: f64,
+) -> f64 {
+    let (p, beta) = weno5_stencils(phim2, phim1, phi, phip1, phip2);
+
+    // global smoothness indicator
+    let tau5 = (beta[0] - beta[2]).abs();
+    let alpha: [f64; 3] = std::array::from_fn(|k| {
+        WENO5_WEIGHTS[k] * (1.0 + (tau5 / (beta[k] + 1e-40)).powi(2))
+    });
+
+    (alpha[0] * p[0] + alpha[1] * p[1] + alpha[2] * p[2])
+        / (alpha[0] + alpha[1] + alpha[2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interp_wenoz() {
+        let result = interp_wenoz(1.0, 2.0, 3.0, 4.0, 5.0);
+        approx::assert_abs_diff_eq!(result, 2.5, epsilon = 1e-15);
+
+        // a step is taken from the smooth side
+        let result = interp_wenoz(1.0, 1.0, 1.0, 0.0, 0.0);
+        approx::assert_abs_diff_eq!(result, 1.0, epsilon = 1e-10);
+    }
+}
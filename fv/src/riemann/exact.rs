@@ -0,0 +1,528 @@
+//! Exact Riemann solver
+//! Reference: Toro (2009), Riemann Solvers and Numerical Methods for Fluid
+//! Dynamics, chapter 4; Toro (2001), Shock-Capturing Methods for
+//! Free-Surface Shallow Flows, chapter 5
+//!
+//! The star state is found by Newton iteration on the pressure (ideal gas)
+//! or the height (shallow water), and the self-similar solution is sampled
+//! at x / t. It serves as a reference solution for verification and as an
+//! interface flux. The transverse velocities and the vapors are carried
+//! with the contact. Vacuum (ideal gas) and dry bed (shallow water) states
+//! on either side or generated in the middle are sampled exactly.
+
+use crate::eos::{
+    eos_trait, ideal_gas::IdealGas, shallow_water::ShallowWater,
+};
+use crate::riemann::flux::{conserved, gather, physical_flux, LOCAL};
+use crate::utils::{
+    common::set_comp,
+    defs::{Real, IDN, IPR, IVX, IVY, NHYDRO, X1DIR, X2DIR},
+};
+
+/// Relative tolerance of the Newton iteration
+const TOL: Real = 1e-12;
+
+/// Maximum number of Newton iterations
+const MAX_ITERS: usize = 100;
+
+/// Equations of state with an exact solution of the Riemann problem
+pub trait ExactRiemann: eos_trait::EquationOfState {
+    /// Primitive state at xi = x / t of the Riemann problem between the
+    /// contiguous primitive cells wl and wr, with the normal velocity ivx
+    fn sample(
+        &self,
+        wl: &[Real; NHYDRO],
+        wr: &[Real; NHYDRO],
+        ivx: usize,
+        xi: Real,
+    ) -> [Real; NHYDRO];
+}
+
+/// # Safety
+///
+/// This function is unsafe because it dereferences raw pointers.
+pub unsafe fn exact<E: ExactRiemann>(
+    flx: *mut Real,
+    wli: &[Real],
+    wri: &[Real],
+    dir: usize,
+    comps: &[usize; NHYDRO],
+    _pos: &[usize],
+    eos: &E,
+) {
+    let ivx = match dir {
+        X1DIR => IVX,
+        X2DIR => IVY,
+        _ => panic!("Invalid direction"),
+    };
+
+    let w = eos.sample(&gather(wli, comps), &gather(wri, comps), ivx, 0.0);
+    let f = physical_flux(
+        &conserved(&w, eos),
+        &w,
+        eos.pressure(&w, &LOCAL),
+        ivx,
+    );
+
+    for (n, f) in comps.iter().zip(f) {
+        set_comp(flx, *n, f);
+    }
+}
+
+/// Solve f(x) = 0 for an increasing f by Newton iteration from the guess
+/// x0, keeping x above the floor xmin. Steps leaving the bracket of the
+/// root found so far are replaced by bisection, and the last iterate is
+/// returned if the iteration does not converge.
+fn newton(
+    x0: Real,
+    xmin: Real,
+    f: impl Fn(Real) -> (Real, Real),
+) -> Real {
+    let (mut lo, mut hi) = (xmin, Real::INFINITY);
+    let mut x = x0;
+    for _ in 0..MAX_ITERS {
+        let (val, der) = f(x);
+        if val == 0.0 {
+            return x;
+        } else if val < 0.0 {
+            lo = lo.max(x);
+        } else {
+            hi = hi.min(x);
+        }
+
+        let mut next = (x - val / der).max(xmin);
+        if 2.0 * (next - x).abs() / (next + x) < TOL {
+            return next;
+        }
+        if !(next > lo && next < hi) {
+            next = if hi.is_finite() { 0.5 * (lo + hi) } else { 2.0 * x };
+        }
+        x = next;
+    }
+    x
+}
+
+impl IdealGas {
+    /// Pressure function f_K(p) and its derivative for the state (rho, p)
+    fn pressure_function(&self, p: Real, rho: Real, pk: Real) -> (Real, Real) {
+        let g = self.gamma;
+        let ck = (g * pk / rho).sqrt();
+
+        if p > pk {
+            // shock
+            let a = 2.0 / ((g + 1.0) * rho);
+            let b = (g - 1.0) / (g + 1.0) * pk;
+            let q = (a / (p + b)).sqrt();
+            ((p - pk) * q, q * (1.0 - 0.5 * (p - pk) / (b + p)))
+        } else {
+            // rarefaction
+            let r = p / pk;
+            (
+                2.0 * ck / (g - 1.0) * (r.powf(0.5 * (g - 1.0) / g) - 1.0),
+                r.powf(-0.5 * (g + 1.0) / g) / (rho * ck),
+            )
+        }
+    }
+
+    /// Sample the left wave at xi for the star state (pm, um). The right
+    /// wave is sampled by mirroring the problem, with sign = -1. A vacuum
+    /// star state has pm = 0.
+    fn sample_wave(
+        &self,
+        w: &[Real; NHYDRO],
+        ivx: usize,
+        pm: Real,
+        um: Real,
+        xi: Real,
+        sign: Real,
+    ) -> [Real; NHYDRO] {
+        let g = self.gamma;
+        let (rho, u, p) = (w[IDN], sign * w[ivx], w[IPR]);
+        let c = (g * p / rho).sqrt();
+        let (um, xi) = (sign * um, sign * xi);
+
+        let (rhos, us, ps) = if pm > p {
+            // shock
+            let s = u - c * ((g + 1.0) / (2.0 * g) * pm / p
+                + (g - 1.0) / (2.0 * g))
+                .sqrt();
+            if xi <= s {
+                (rho, u, p)
+            } else {
+                let r = (g - 1.0) / (g + 1.0);
+                (rho * (pm / p + r) / (r * pm / p + 1.0), um, pm)
+            }
+        } else {
+            // rarefaction, possibly into a vacuum
+            let cm = c * (pm / p).powf(0.5 * (g - 1.0) / g);
+            let tail = if pm > 0.0 {
+                um - cm
+            } else {
+                u + 2.0 * c / (g - 1.0)
+            };
+            if xi <= u - c {
+                (rho, u, p)
+            } else if xi >= tail {
+                let us = if pm > 0.0 { um } else { 0.0 };
+                (rho * (pm / p).powf(1.0 / g), us, pm)
+            } else {
+                let r =
+                    2.0 / (g + 1.0) + (g - 1.0) / ((g + 1.0) * c) * (u - xi);
+                (
+                    rho * r.powf(2.0 / (g - 1.0)),
+                    2.0 / (g + 1.0) * (c + 0.5 * (g - 1.0) * u + xi),
+                    p * r.powf(2.0 * g / (g - 1.0)),
+                )
+            }
+        };
+
+        let mut ws = *w;
+        ws[IDN] = rhos;
+        ws[ivx] = sign * us;
+        ws[IPR] = ps;
+        ws
+    }
+}
+
+impl ExactRiemann for IdealGas {
+    fn sample(
+        &self,
+        wl: &[Real; NHYDRO],
+        wr: &[Real; NHYDRO],
+        ivx: usize,
+        xi: Real,
+    ) -> [Real; NHYDRO] {
+        let g = self.gamma;
+        let (rhol, ul, pl) = (wl[IDN], wl[ivx], wl[IPR]);
+        let (rhor, ur, pr) = (wr[IDN], wr[ivx], wr[IPR]);
+
+        // vacuum on either side or generated in the middle (Toro 2009, 4.6)
+        if rhol <= 0.0 || pl <= 0.0 {
+            return self.sample_wave(wr, ivx, 0.0, 0.0, xi, -1.0);
+        }
+        if rhor <= 0.0 || pr <= 0.0 {
+            return self.sample_wave(wl, ivx, 0.0, 0.0, xi, 1.0);
+        }
+        let cl = (g * pl / rhol).sqrt();
+        let cr = (g * pr / rhor).sqrt();
+        if 2.0 / (g - 1.0) * (cl + cr) <= ur - ul {
+            let front = 0.5 * (ul + ur) + (cl - cr) / (g - 1.0);
+            return if xi <= front {
+                self.sample_wave(wl, ivx, 0.0, 0.0, xi, 1.0)
+            } else {
+                self.sample_wave(wr, ivx, 0.0, 0.0, xi, -1.0)
+            };
+        }
+
+        // primitive variable guess
+        let p0 = 0.5 * (pl + pr)
+            - 0.125 * (ur - ul) * (rhol + rhor) * (cl + cr);
+
+        let pm = newton(p0.max(TOL), TOL, |p| {
+            let (fl, dfl) = self.pressure_function(p, rhol, pl);
+            let (fr, dfr) = self.pressure_function(p, rhor, pr);
+            (fl + fr + ur - ul, dfl + dfr)
+        });
+        let um = 0.5 * (ul + ur)
+            + 0.5
+                * (self.pressure_function(pm, rhor, pr).0
+                    - self.pressure_function(pm, rhol, pl).0);
+
+        if xi <= um {
+            self.sample_wave(wl, ivx, pm, um, xi, 1.0)
+        } else {
+            self.sample_wave(wr, ivx, pm, um, xi, -1.0)
+        }
+    }
+}
+
+impl ShallowWater {
+    /// Height function f_K(h) and its derivative for the height hk
+    fn height_function(&self, h: Real, hk: Real) -> (Real, Real) {
+        let g = self.grav;
+        if h > hk {
+            // shock
+            let q = (0.5 * g * (h + hk) / (h * hk)).sqrt();
+            ((h - hk) * q, q - 0.25 * g * (h - hk) / (h * h * q))
+        } else {
+            // rarefaction
+            (2.0 * ((g * h).sqrt() - (g * hk).sqrt()), (g / h).sqrt())
+        }
+    }
+
+    /// Sample the left wave at xi for the star state (hm, um). The right
+    /// wave is sampled by mirroring the problem, with sign = -1. A dry star
+    /// state has hm = 0.
+    fn sample_wave(
+        &self,
+        w: &[Real; NHYDRO],
+        ivx: usize,
+        hm: Real,
+        um: Real,
+        xi: Real,
+        sign: Real,
+    ) -> [Real; NHYDRO] {
+        let g = self.grav;
+        let (h, u) = (w[IDN], sign * w[ivx]);
+        let c = (g * h).sqrt();
+        let (um, xi) = (sign * um, sign * xi);
+
+        let (hs, us) = if hm > h {
+            // shock
+            let s = u - c * (0.5 * hm * (hm + h)).sqrt() / h;
+            if xi <= s {
+                (h, u)
+            } else {
+                (hm, um)
+            }
+        } else {
+            // rarefaction, possibly into a dry bed
+            let cm = (g * hm).sqrt();
+            let tail = if hm > 0.0 { um - cm } else { u + 2.0 * c };
+            if xi <= u - c {
+                (h, u)
+            } else if xi >= tail {
+                (hm, if hm > 0.0 { um } else { 0.0 })
+            } else {
+                let cs = (u + 2.0 * c - xi) / 3.0;
+                (cs * cs / g, (u + 2.0 * c + 2.0 * xi) / 3.0)
+            }
+        };
+
+        let mut ws = *w;
+        ws[IDN] = hs;
+        ws[ivx] = sign * us;
+        ws
+    }
+}
+
+impl ExactRiemann for ShallowWater {
+    fn sample(
+        &self,
+        wl: &[Real; NHYDRO],
+        wr: &[Real; NHYDRO],
+        ivx: usize,
+        xi: Real,
+    ) -> [Real; NHYDRO] {
+        let g = self.grav;
+        let (hl, ul) = (wl[IDN].max(0.0), wl[ivx]);
+        let (hr, ur) = (wr[IDN].max(0.0), wr[ivx]);
+        let cl = (g * hl).sqrt();
+        let cr = (g * hr).sqrt();
+
+        // dry bed on either side or generated in the middle
+        if hl == 0.0 {
+            return self.sample_wave(wr, ivx, 0.0, 0.0, xi, -1.0);
+        }
+        if hr == 0.0 {
+            return self.sample_wave(wl, ivx, 0.0, 0.0, xi, 1.0);
+        }
+        if 2.0 * (cl + cr) <= ur - ul {
+            return if xi <= 0.5 * (ul + 2.0 * cl + ur - 2.0 * cr) {
+                self.sample_wave(wl, ivx, 0.0, 0.0, xi, 1.0)
+            } else {
+                self.sample_wave(wr, ivx, 0.0, 0.0, xi, -1.0)
+            };
+        }
+
+        // two-rarefaction guess
+        let h0 = (0.5 * (cl + cr) - 0.25 * (ur - ul)).powi(2) / g;
+
+        let hm = newton(h0, TOL, |h| {
+            let (fl, dfl) = self.height_function(h, hl);
+            let (fr, dfr) = self.height_function(h, hr);
+            (fl + fr + ur - ul, dfl + dfr)
+        });
+        let um = 0.5 * (ul + ur)
+            + 0.5 * (self.height_function(hm, hr).0
+                - self.height_function(hm, hl).0);
+
+        if xi <= um {
+            self.sample_wave(wl, ivx, hm, um, xi, 1.0)
+        } else {
+            self.sample_wave(wr, ivx, hm, um, xi, -1.0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::riemann::hllc::hllc;
+    use crate::utils::common::dry_state;
+
+    /// Star states of the Toro test problems (Toro 2009, table 4.3)
+    #[test]
+    fn test_toro_star_states() {
+        let eos = IdealGas { gamma: 1.4 };
+
+        // tol is the precision of the tabulated p*, the last problem also
+        // has rounded initial states
+        for (wl, wr, pm, um, tol) in [
+            (
+                dry_state([1.0, 0.0, 0.0, 0.0, 1.0]),
+                dry_state([0.125, 0.0, 0.0, 0.0, 0.1]),
+                0.30313,
+                0.92745,
+                5e-6,
+            ),
+            (
+                dry_state([1.0, -2.0, 0.0, 0.0, 0.4]),
+                dry_state([1.0, 2.0, 0.0, 0.0, 0.4]),
+                0.00189,
+                0.0,
+                5e-6,
+            ),
+            (
+                dry_state([1.0, 0.0, 0.0, 0.0, 1000.0]),
+                dry_state([1.0, 0.0, 0.0, 0.0, 0.01]),
+                460.894,
+                19.5975,
+                5e-4,
+            ),
+            (
+                dry_state([5.99924, 19.5975, 0.0, 0.0, 460.894]),
+                dry_state([5.99242, -6.19633, 0.0, 0.0, 46.0950]),
+                1691.64,
+                8.68975,
+                1e-2,
+            ),
+        ] {
+            // the contact moves with the star velocity
+            let left = eos.sample(&wl, &wr, IVX, um - 1e-9);
+            let right = eos.sample(&wl, &wr, IVX, um + 1e-9);
+            for w in [left, right] {
+                approx::assert_abs_diff_eq!(w[IPR], pm, epsilon = tol);
+                approx::assert_abs_diff_eq!(w[IVX], um, epsilon = 1e-4);
+            }
+        }
+    }
+
+    /// Sod shock tube sampled across the waves
+    #[test]
+    fn test_sod_sample() {
+        let eos = IdealGas { gamma: 1.4 };
+        let wl = dry_state([1.0, 0.0, 0.3, 0.0, 1.0]);
+        let wr = dry_state([0.125, 0.0, -0.3, 0.0, 0.1]);
+        let sample = |xi| eos.sample(&wl, &wr, IVX, xi);
+
+        assert_eq!(sample(-2.0), wl);
+        assert_eq!(sample(2.0), wr);
+
+        // star regions
+        approx::assert_relative_eq!(sample(0.5)[IDN], 0.42632, epsilon = 1e-5);
+        approx::assert_relative_eq!(sample(1.5)[IDN], 0.26557, epsilon = 1e-5);
+        assert_eq!(sample(0.5)[IVY], 0.3);
+        assert_eq!(sample(1.5)[IVY], -0.3);
+
+        // the rarefaction fan is continuous
+        let head = -(1.4 as Real).sqrt();
+        approx::assert_abs_diff_eq!(
+            sample(head + 1e-10)[IDN],
+            1.0,
+            epsilon = 1e-9
+        );
+    }
+
+    /// The exact flux agrees with HLLC at an isolated contact
+    #[test]
+    fn test_flux_contact() {
+        let eos = IdealGas { gamma: 1.4 };
+        let wl = dry_state([1.0, 0.5, 0.0, 0.0, 1.0]);
+        let wr = dry_state([0.1, 0.5, 0.0, 0.0, 1.0]);
+
+        let mut flx = [0.0; NHYDRO];
+        let mut expected = [0.0; NHYDRO];
+        unsafe {
+            exact(flx.as_mut_ptr(), &wl, &wr, X1DIR, &LOCAL, &[0], &eos);
+            hllc(expected.as_mut_ptr(), &wl, &wr, X1DIR, &LOCAL, &[0], &eos);
+        }
+
+        for (f, e) in flx.iter().zip(expected) {
+            approx::assert_abs_diff_eq!(*f, e, epsilon = 1e-12);
+        }
+    }
+
+    /// Stoker dam break, with g = 1, h = 2 | 1
+    #[test]
+    fn test_dam_break() {
+        let eos = ShallowWater { grav: 1.0 };
+        let wl = dry_state([2.0, 0.0, 0.0, 0.0, 0.0]);
+        let wr = dry_state([1.0, 0.0, 0.0, 0.0, 0.0]);
+
+        let star = eos.sample(&wl, &wr, IVY, 0.0);
+        approx::assert_relative_eq!(star[IDN], 1.45384, epsilon = 1e-5);
+        approx::assert_relative_eq!(star[IVY], 0.41692, epsilon = 1e-5);
+    }
+
+    /// Ritter dam break onto a dry bed, with g = 1, h = 1 | 0
+    #[test]
+    fn test_dry_dam_break() {
+        let eos = ShallowWater { grav: 1.0 };
+        let wl = dry_state([1.0, 0.0, 0.0, 0.0, 0.0]);
+        let wr = dry_state([0.0, 0.0, 0.0, 0.0, 0.0]);
+
+        // h = (2 - xi)^2 / 9 and u = 2 (1 + xi) / 3 in the fan
+        for xi in [-0.5, 0.0, 1.0, 1.9] {
+            let w = eos.sample(&wl, &wr, IVX, xi);
+            approx::assert_relative_eq!(w[IDN], (2.0 - xi).powi(2) / 9.0);
+            approx::assert_relative_eq!(w[IVX], 2.0 * (1.0 + xi) / 3.0);
+        }
+
+        assert_eq!(eos.sample(&wl, &wr, IVX, 2.5)[IDN], 0.0);
+        assert_eq!(eos.sample(&wl, &wr, IVX, -1.5), wl);
+    }
+
+    /// Two strong rarefactions open a vacuum between them, and the flux
+    /// through its middle vanishes
+    #[test]
+    fn test_vacuum_generation() {
+        let eos = IdealGas { gamma: 1.4 };
+        let wl = dry_state([1.0, -4.0, 0.0, 0.0, 0.4]);
+        let wr = dry_state([1.0, 4.0, 0.0, 0.0, 0.4]);
+        let c: Real = (1.4 * 0.4 as Real).sqrt();
+
+        // the left fan ends in a vacuum at ul + 2 cl / (gamma - 1)
+        let front = -4.0 + 5.0 * c;
+        let w = eos.sample(&wl, &wr, IVX, front - 1e-3);
+        assert!(w[IDN] > 0.0 && w[IDN] < 1e-6);
+        assert_eq!(eos.sample(&wl, &wr, IVX, 0.0)[IDN], 0.0);
+        assert_eq!(eos.sample(&wl, &wr, IVX, -4.0 - c - 1e-3), wl);
+
+        let mut flx = [1.0; NHYDRO];
+        unsafe {
+            exact(flx.as_mut_ptr(), &wl, &wr, X1DIR, &LOCAL, &[0], &eos);
+        }
+        assert_eq!(flx, [0.0; NHYDRO]);
+    }
+
+    /// A gas expanding into a vacuum on the right
+    #[test]
+    fn test_vacuum_right() {
+        let eos = IdealGas { gamma: 1.4 };
+        let wl = dry_state([1.0, 0.0, 0.0, 0.0, 1.0]);
+        let wr = [0.0; NHYDRO];
+        let c: Real = 1.4_f64.sqrt();
+
+        // sonic point of the fan, u = c = 2 c_l / (gamma + 1)
+        let w = eos.sample(&wl, &wr, IVX, 0.0);
+        approx::assert_relative_eq!(w[IVX], 2.0 * c / 2.4, epsilon = 1e-14);
+        approx::assert_relative_eq!(
+            w[IDN],
+            (2.0 / 2.4 as Real).powf(5.0),
+            epsilon = 1e-14
+        );
+        assert_eq!(eos.sample(&wl, &wr, IVX, 5.0 * c + 1e-3), wr);
+    }
+
+    /// Newton steps leaving the bracket of the root fall back to
+    /// bisection, where plain Newton diverges for the cube root
+    #[test]
+    fn test_newton_bisection() {
+        let x = newton(3.0, 0.0, |x| {
+            let y = (x - 1.0).cbrt();
+            (y, 1.0 / (3.0 * y * y))
+        });
+        approx::assert_abs_diff_eq!(x, 1.0, epsilon = 1e-9);
+    }
+}
@@ -0,0 +1,104 @@
+//! Shared pieces of the flux-based Riemann solvers
+//!
+//! The solvers gather the interface states into contiguous cells, whose
+//! components are addressed by the offsets LOCAL.
+
+use crate::eos::eos_trait;
+use crate::utils::defs::{Real, IPR, NHYDRO};
+
+/// Component offsets of a contiguous cell
+pub const LOCAL: [usize; NHYDRO] = {
+    let mut comps = [0; NHYDRO];
+    let mut n = 0;
+    while n < NHYDRO {
+        comps[n] = n;
+        n += 1;
+    }
+    comps
+};
+
+/// Gather the cell w addressed by comps into a contiguous cell
+pub fn gather(w: &[Real], comps: &[usize; NHYDRO]) -> [Real; NHYDRO] {
+    comps.map(|n| w[n])
+}
+
+/// Conserved variables of a contiguous primitive cell
+pub fn conserved<E: eos_trait::EquationOfState>(
+    w: &[Real; NHYDRO],
+    eos: &E,
+) -> [Real; NHYDRO] {
+    let mut u = [0.0; NHYDRO];
+    unsafe { eos.primitive_to_conserved(u.as_mut_ptr(), w, &LOCAL) };
+    u
+}
+
+/// Flux of a contiguous cell in the direction of the velocity ivx
+///
+/// All conserved components are densities advected with the flow, the
+/// pressure pres adds to the normal momentum and does work on the energy.
+pub fn physical_flux(
+    u: &[Real; NHYDRO],
+    w: &[Real; NHYDRO],
+    pres: Real,
+    ivx: usize,
+) -> [Real; NHYDRO] {
+    let mut f = u.map(|u| u * w[ivx]);
+    f[ivx] += pres;
+    f[IPR] += pres * w[ivx];
+    f
+}
+
+/// First order Godunov solution of a one-dimensional Riemann problem on
+/// [0, 1] with the discontinuity at x = 0.5 and outflow boundaries
+#[cfg(test)]
+pub fn shock_tube<E, F>(
+    eos: &E,
+    solver: F,
+    wl: [Real; NHYDRO],
+    wr: [Real; NHYDRO],
+    tmax: Real,
+) -> Vec<[Real; NHYDRO]>
+where
+    E: eos_trait::EquationOfState,
+    F: Fn(*mut Real, &[Real], &[Real]),
+{
+    use crate::utils::defs::{IDN, IVX};
+
+    let nx = 400;
+    let dx = 1.0 / nx as Real;
+
+    let mut w: Vec<[Real; NHYDRO]> =
+        (0..nx).map(|i| if i < nx / 2 { wl } else { wr }).collect();
+    let mut u: Vec<[Real; NHYDRO]> =
+        w.iter().map(|w| conserved(w, eos)).collect();
+
+    let mut time = 0.0;
+    while time < tmax {
+        let smax = w
+            .iter()
+            .map(|w| w[IVX].abs() + eos.sound_speed(w, &LOCAL))
+            .fold(0.0, Real::max);
+        let dt = (0.4 * dx / smax).min(tmax - time);
+
+        let flux: Vec<[Real; NHYDRO]> = (0..=nx)
+            .map(|i| {
+                let mut flx = [0.0; NHYDRO];
+                solver(flx.as_mut_ptr(), &w[i.max(1) - 1], &w[i.min(nx - 1)]);
+                flx
+            })
+            .collect();
+
+        for (i, u) in u.iter_mut().enumerate() {
+            for n in 0..NHYDRO {
+                u[n] -= dt / dx * (flux[i + 1][n] - flux[i][n]);
+            }
+        }
+        for (w, u) in w.iter_mut().zip(&u) {
+            unsafe { eos.conserved_to_primitive(w.as_mut_ptr(), u, &LOCAL) };
+            assert!(w[IDN] >= 0.0, "negative density");
+        }
+        time += dt;
+    }
+
+    w
+}
@@ -0,0 +1,212 @@
+//! HLLC Riemann solver
+//! Reference: Toro, Spruce & Speares (1994)
+//!
+//! Harten-Lax-van Leer solver with the contact wave restored, for the Euler
+//! equations. The signal speeds are the Davis estimates from the sound speed
+//! of the equation of state. The vapor components are carried as passive
+//! densities across the contact.
+
+use crate::eos::eos_trait;
+use crate::riemann::flux::{conserved, gather, physical_flux, LOCAL};
+use crate::utils::{
+    common::set_comp,
+    defs::{Real, IDN, IPR, IVX, IVY, NHYDRO, X1DIR, X2DIR},
+};
+
+/// # Safety
+///
+/// This function is unsafe because it dereferences raw pointers.
+pub unsafe fn hllc<E: eos_trait::EquationOfState>(
+    flx: *mut Real,
+    wli: &[Real],
+    wri: &[Real],
+    dir: usize,
+    comps: &[usize; NHYDRO],
+    _pos: &[usize],
+    eos: &E,
+) {
+    let ivx = match dir {
+        X1DIR => IVX,
+        X2DIR => IVY,
+        _ => panic!("Invalid direction"),
+    };
+
+    let wl = gather(wli, comps);
+    let wr = gather(wri, comps);
+    let ul = conserved(&wl, eos);
+    let ur = conserved(&wr, eos);
+
+    let cl = eos.sound_speed(&wl, &LOCAL);
+    let cr = eos.sound_speed(&wr, &LOCAL);
+
+    let sl = (wl[ivx] - cl).min(wr[ivx] - cr);
+    let sr = (wl[ivx] + cl).max(wr[ivx] + cr);
+
+    let (rhol, rhor) = (wl[IDN], wr[IDN]);
+    let pl = eos.pressure(&wl, &LOCAL);
+    let pr = eos.pressure(&wr, &LOCAL);
+    let (vl, vr) = (wl[ivx], wr[ivx]);
+
+    // contact speed
+    let sm = (pr - pl + rhol * vl * (sl - vl) - rhor * vr * (sr - vr))
+        / (rhol * (sl - vl) - rhor * (sr - vr));
+
+    let fl = physical_flux(&ul, &wl, pl, ivx);
+    let fr = physical_flux(&ur, &wr, pr, ivx);
+
+    let f = if sl >= 0.0 {
+        fl
+    } else if sr <= 0.0 {
+        fr
+    } else if sm >= 0.0 {
+        let us = star_state(&ul, &wl, pl, ivx, sl, sm);
+        std::array::from_fn(|n| fl[n] + sl * (us[n] - ul[n]))
+    } else {
+        let us = star_state(&ur, &wr, pr, ivx, sr, sm);
+        std::array::from_fn(|n| fr[n] + sr * (us[n] - ur[n]))
+    };
+
+    for (n, f) in comps.iter().zip(f) {
+        set_comp(flx, *n, f);
+    }
+}
+
+/// Conserved state between the signal speed s and the contact speed sm
+fn star_state(
+    u: &[Real; NHYDRO],
+    w: &[Real; NHYDRO],
+    pres: Real,
+    ivx: usize,
+    s: Real,
+    sm: Real,
+) -> [Real; NHYDRO] {
+    let ratio = (s - w[ivx]) / (s - sm);
+    let mut us = u.map(|u| u * ratio);
+    us[ivx] = ratio * w[IDN] * sm;
+    us[IPR] = ratio
+        * (u[IPR] + (sm - w[ivx]) * (w[IDN] * sm + pres / (s - w[ivx])));
+    us
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eos::{eos_trait::EquationOfState, ideal_gas::IdealGas};
+    use crate::riemann::flux;
+    use crate::utils::{common::dry_state, defs::IVZ};
+
+    fn shock_tube(
+        wl: [Real; NHYDRO],
+        wr: [Real; NHYDRO],
+        tmax: Real,
+    ) -> Vec<[Real; NHYDRO]> {
+        let eos = IdealGas { gamma: 1.4 };
+        let solver = |flx, l: &[Real], r: &[Real]| unsafe {
+            hllc(flx, l, r, X1DIR, &LOCAL, &[0], &eos)
+        };
+        flux::shock_tube(&eos, solver, wl, wr, tmax)
+    }
+
+    /// The flux of identical left and right states is the physical flux
+    #[test]
+    fn test_consistency() {
+        let eos = IdealGas { gamma: 1.4 };
+        let w = dry_state([1.2, 0.3, -0.2, 0.1, 2.0]);
+        let e = eos.energy(&w, &LOCAL);
+
+        let flux = |dir| {
+            let mut flx = [0.0; NHYDRO];
+            unsafe {
+                hllc(flx.as_mut_ptr(), &w, &w, dir, &LOCAL, &[0], &eos);
+            }
+            flx
+        };
+
+        let flx = flux(X1DIR);
+        approx::assert_relative_eq!(flx[IDN], 1.2 * 0.3);
+        approx::assert_relative_eq!(flx[IVX], 1.2 * 0.09 + 2.0);
+        approx::assert_relative_eq!(flx[IVY], -1.2 * 0.3 * 0.2);
+        approx::assert_relative_eq!(flx[IPR], 0.3 * (e + 2.0));
+
+        let flx = flux(X2DIR);
+        approx::assert_relative_eq!(flx[IDN], -1.2 * 0.2);
+        approx::assert_relative_eq!(flx[IVX], -1.2 * 0.2 * 0.3);
+        approx::assert_relative_eq!(flx[IVY], 1.2 * 0.04 + 2.0);
+        approx::assert_relative_eq!(flx[IVZ], -1.2 * 0.2 * 0.1);
+    }
+
+    /// An isolated contact is resolved exactly
+    #[test]
+    fn test_stationary_contact() {
+        let eos = IdealGas { gamma: 1.4 };
+        let wl = dry_state([1.0, 0.0, 0.5, 0.0, 1.0]);
+        let wr = dry_state([0.1, 0.0, -0.5, 0.0, 1.0]);
+
+        let mut flx = [0.0; NHYDRO];
+        unsafe {
+            hllc(flx.as_mut_ptr(), &wl, &wr, X1DIR, &LOCAL, &[0], &eos);
+        }
+
+        approx::assert_abs_diff_eq!(flx[IDN], 0.0);
+        approx::assert_abs_diff_eq!(flx[IVX], 1.0);
+        approx::assert_abs_diff_eq!(flx[IVY], 0.0);
+        approx::assert_abs_diff_eq!(flx[IPR], 0.0);
+    }
+
+    /// Sod shock tube (Toro test 1), star region
+    /// p* = 0.30313, u* = 0.92745, rho*L = 0.42632, rho*R = 0.26557
+    #[test]
+    fn test_sod() {
+        let w = shock_tube(
+            dry_state([1.0, 0.0, 0.0, 0.0, 1.0]),
+            dry_state([0.125, 0.0, 0.0, 0.0, 0.1]),
+            0.2,
+        );
+
+        // contact at x = 0.685, shock at x = 0.850
+        let left = w[(0.62 * 400.0) as usize];
+        let right = w[(0.77 * 400.0) as usize];
+
+        approx::assert_relative_eq!(left[IDN], 0.42632, max_relative = 0.01);
+        approx::assert_relative_eq!(right[IDN], 0.26557, max_relative = 0.01);
+        for w in [left, right] {
+            approx::assert_relative_eq!(w[IVX], 0.92745, max_relative = 0.01);
+            approx::assert_relative_eq!(w[IPR], 0.30313, max_relative = 0.01);
+        }
+    }
+
+    /// Toro test 2, two symmetric rarefactions leaving a near vacuum
+    /// p* = 0.00189, u* = 0
+    #[test]
+    fn test_toro_123() {
+        let w = shock_tube(
+            dry_state([1.0, -2.0, 0.0, 0.0, 0.4]),
+            dry_state([1.0, 2.0, 0.0, 0.0, 0.4]),
+            0.15,
+        );
+
+        for w in &w {
+            assert!(w[IDN] > 0.0 && w[IPR] > 0.0);
+        }
+        for (wl, wr) in w.iter().zip(w.iter().rev()) {
+            approx::assert_abs_diff_eq!(wl[IDN], wr[IDN], epsilon = 1e-10);
+            approx::assert_abs_diff_eq!(wl[IVX], -wr[IVX], epsilon = 1e-10);
+        }
+    }
+
+    /// Toro test 3, left half of the blast wave problem
+    /// p* = 460.894, u* = 19.5975
+    #[test]
+    fn test_toro_blast() {
+        let w = shock_tube(
+            dry_state([1.0, 0.0, 0.0, 0.0, 1000.0]),
+            dry_state([1.0, 0.0, 0.0, 0.0, 0.01]),
+            0.012,
+        );
+
+        // contact at x = 0.735, shock at x = 0.782
+        let star = w[(0.70 * 400.0) as usize];
+        approx::assert_relative_eq!(star[IVX], 19.5975, max_relative = 0.02);
+        approx::assert_relative_eq!(star[IPR], 460.894, max_relative = 0.02);
+    }
+}
@@ -1,97 +1,225 @@
 //! LMARS Riemann solver
 //! Reference: Chen et al. (2017)
-//! \todo Does not work now. Needs fix
+//!
+//! Low-Mach approximate Riemann solver for stratified atmospheres. The
+//! vapor mass mixing ratios 1..=NVAPOR are carried with the dry air and the
+//! density component of the flux is the dry air flux.
 
-use crate::{defs, variable::Variable};
+use crate::eos::eos_trait;
+use crate::utils::{
+    common::set_comp,
+    defs::{Real, IDN, IPR, IVX, IVY, IVZ, NHYDRO, X1DIR, X2DIR},
+};
 
-fn riemann_solver_lmars(
-    flx: &mut Variable,
-    wli: &Variable,
-    wri: &Variable,
-    ivx: usize,
+/// # Safety
+///
+/// This function is unsafe because it dereferences raw pointers.
+pub unsafe fn lmars<E: eos_trait::EquationOfState>(
+    flx: *mut Real,
+    wli: &[Real],
+    wri: &[Real],
+    dir: usize,
+    comps: &[usize; NHYDRO],
+    _pos: &[usize],
+    eos: &E,
 ) {
-    let ivy = IVX + ((ivx - IVX) + 1) % 3;
-    let ivz = IVX + ((ivx - IVX) + 2) % 3;
-
-    // Assuming Thermodynamics struct and pmy_block are defined elsewhere
-    // let pthermo = pmy_block.pimpl.pthermo;
-    // let gamma = pmy_block.peos.get_gamma();
-
-    // Assuming gamma is defined as a constant
-    let gamma: Real = 1.4;
-
-    // Correction for gamma
-    // left
-    let mut fsig = 1.0;
-    let mut feps = 1.0;
-    for n in 1..=NVAPOR {
-        // Assuming pthermo.get_cv_ratio(n) and pthermo.get_mass_ratio(n) are
-        // defined as constants
-        let cv_ratio_n: Real = 1.0; // Replace with the correct value
-        let mass_ratio_n: Real = 1.0; // Replace with the correct value
-        fsig += wli[n] * (cv_ratio_n - 1.0);
-        feps += wli[n] * (1.0 / mass_ratio_n - 1.0);
+    let idn = comps[IDN];
+    let ipr = comps[IPR];
+
+    let (ivx, ivy, ivz) = match dir {
+        X1DIR => (comps[IVX], comps[IVY], comps[IVZ]),
+        X2DIR => (comps[IVY], comps[IVZ], comps[IVX]),
+        _ => panic!("Invalid direction"),
+    };
+
+    let pl = eos.pressure(wli, comps);
+    let pr = eos.pressure(wri, comps);
+
+    // effective adiabatic index, gamma = 1 + 1 / kappa
+    let gammal = eos.sound_speed(wli, comps).powi(2) * wli[idn] / pl;
+    let gammar = eos.sound_speed(wri, comps).powi(2) * wri[idn] / pr;
+
+    // enthalpy
+    let hl = (eos.energy(wli, comps) + pl) / wli[idn];
+    let hr = (eos.energy(wri, comps) + pr) / wri[idn];
+
+    let rhobar = 0.5 * (wli[idn] + wri[idn]);
+    let cbar = (0.25 * (gammal + gammar) * (pl + pr) / rhobar).sqrt();
+    let pbar = 0.5 * (pl + pr) + 0.5 * (rhobar * cbar) * (wli[ivx] - wri[ivx]);
+    let ubar =
+        0.5 * (wli[ivx] + wri[ivx]) + 0.5 / (rhobar * cbar) * (pl - pr);
+
+    // upwind state
+    let (w, h) = if ubar > 0.0 { (wli, hl) } else { (wri, hr) };
+    let vapors = &comps[1..IVX];
+
+    // mass mixing ratio of dry air
+    let rd = 1.0 - vapors.iter().map(|n| w[*n]).sum::<Real>();
+
+    let mflx = ubar * w[idn];
+    set_comp(flx, idn, mflx * rd);
+    for n in vapors {
+        set_comp(flx, *n, mflx * w[*n]);
     }
-    let kappal = 1.0 / (gamma - 1.0) * fsig / feps;
-
-    // right
-    fsig = 1.0;
-    feps = 1.0;
-    for n in 1..=NVAPOR {
-        // Assuming pthermo.get_cv_ratio(n) and pthermo.get_mass_ratio(n) are
-        // defined as constants
-        let cv_ratio_n: Real = 1.0; // Replace with the correct value
-        let mass_ratio_n: Real = 1.0; // Replace with the correct value
-        fsig += wri[n] * (cv_ratio_n - 1.0);
-        feps += wri[n] * (1.0 / mass_ratio_n - 1.0);
+    set_comp(flx, ivx, mflx * w[ivx] + pbar);
+    set_comp(flx, ivy, mflx * w[ivy]);
+    set_comp(flx, ivz, mflx * w[ivz]);
+    set_comp(flx, ipr, mflx * h);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eos::{eos_trait::EquationOfState, ideal_gas::IdealGas};
+    use crate::riemann::flux::LOCAL;
+    use crate::utils::common::dry_state;
+
+    fn flux(wl: &[Real], wr: &[Real], dir: usize) -> [Real; NHYDRO] {
+        let mut flx = [0.0; NHYDRO];
+        let eos = IdealGas { gamma: 1.4 };
+        unsafe {
+            lmars(flx.as_mut_ptr(), wl, wr, dir, &LOCAL, &[0], &eos);
+        }
+        flx
     }
-    let kappar = 1.0 / (gamma - 1.0) * fsig / feps;
 
-    // enthalpy
-    let hl = wli[IPR] / wli[IDN] * (kappal + 1.0)
-        + 0.5 * (wli[ivx].powi(2) + wli[ivy].powi(2) + wli[ivz].powi(2));
-    let hr = wri[IPR] / wri[IDN] * (kappar + 1.0)
-        + 0.5 * (wri[ivx].powi(2) + wri[ivy].powi(2) + wri[ivz].powi(2));
-
-    let rhobar = 0.5 * (wli[IDN] + wri[IDN]);
-    let cbar = (0.5
-        * (1.0 + (1.0 / kappar + 1.0 / kappal) / 2.0)
-        * (wli[IPR] + wri[IPR])
-        / rhobar)
-        .sqrt();
-    let pbar = 0.5 * (wli[IPR] + wri[IPR])
-        + 0.5 * (rhobar * cbar) * (wli[ivx] - wri[ivx]);
-    let ubar = 0.5 * (wli[ivx] + wri[ivx])
-        + 0.5 / (rhobar * cbar) * (wli[IPR] - wri[IPR]);
-
-    let mut rd = 1.0;
-    if ubar > 0.0 {
-        // volume mixing ratio to mass mixing ratio
-        for n in 1..=NVAPOR {
-            rd -= wli[n];
+    /// The flux of identical left and right states is the physical flux
+    #[test]
+    fn test_consistency() {
+        let eos = IdealGas { gamma: 1.4 };
+        let w = dry_state([1.2, 0.3, -0.2, 0.1, 1e5]);
+        let e = eos.energy(&w, &LOCAL);
+
+        let flx = flux(&w, &w, X1DIR);
+        approx::assert_relative_eq!(flx[IDN], 1.2 * 0.3);
+        approx::assert_relative_eq!(flx[IVX], 1.2 * 0.09 + 1e5);
+        approx::assert_relative_eq!(flx[IVY], -1.2 * 0.3 * 0.2);
+        approx::assert_relative_eq!(flx[IPR], 0.3 * (e + 1e5));
+
+        let flx = flux(&w, &w, X2DIR);
+        approx::assert_relative_eq!(flx[IDN], -1.2 * 0.2);
+        approx::assert_relative_eq!(flx[IVY], 1.2 * 0.04 + 1e5);
+        approx::assert_relative_eq!(flx[IVZ], -1.2 * 0.2 * 0.1);
+        approx::assert_relative_eq!(flx[IVX], -1.2 * 0.2 * 0.3);
+    }
+
+    /// A discretely hydrostatic column with a slow horizontal wind, where
+    /// pressure and density both decrease with height. The face states are
+    /// extrapolated hydrostatically from the cell centers, so the interfaces
+    /// carry no mass or energy and the pressure flux difference balances the
+    /// weight of each cell to round-off. The cell-centered states jump in
+    /// pressure across the faces and drive the upward flux of the LMARS
+    /// formulas, with the sound speed of the ideal gas.
+    #[test]
+    fn test_low_mach_stratified() {
+        let (grav, dz, scale_height) = (9.8, 500.0, 8.0e3);
+        let rho0: Real = 1.2;
+        let u0 = 10.0;
+
+        let nz = 20;
+        let rho: Vec<Real> = (0..nz)
+            .map(|k| rho0 * (-(k as Real + 0.5) * dz / scale_height).exp())
+            .collect();
+        let mut pres = vec![rho0 * grav * scale_height];
+        for k in 1..nz {
+            pres.push(pres[k - 1] - 0.5 * grav * dz * (rho[k - 1] + rho[k]));
         }
 
-        flx[IDN] = ubar * wli[IDN] * rd;
-        for n in 1..=NVAPOR {
-            flx[n] = ubar * wli[IDN] * wli[n];
+        // fluxes through the faces between cells k and k + 1
+        let flx: Vec<[Real; NHYDRO]> = (0..nz - 1)
+            .map(|k| {
+                let pl = pres[k] - 0.5 * grav * dz * rho[k];
+                let pr = pres[k + 1] + 0.5 * grav * dz * rho[k + 1];
+                let wl = dry_state([rho[k], u0, 0.0, 0.0, pl]);
+                let wr = dry_state([rho[k + 1], u0, 0.0, 0.0, pr]);
+                flux(&wl, &wr, X2DIR)
+            })
+            .collect();
+
+        // round-off relative to the acoustic flux scales
+        let cs = (1.4 * pres[0] / rho0).sqrt();
+        let eps = 1e-14 * rho0 * cs;
+        for f in flx.iter() {
+            approx::assert_abs_diff_eq!(f[IDN], 0.0, epsilon = eps);
+            approx::assert_abs_diff_eq!(f[IVX], 0.0, epsilon = eps * u0);
+            approx::assert_abs_diff_eq!(f[IPR], 0.0, epsilon = eps * cs * cs);
         }
-        flx[ivx] = ubar * wli[IDN] * wli[ivx] + pbar;
-        flx[ivy] = ubar * wli[IDN] * wli[ivy];
-        flx[ivz] = ubar * wli[IDN] * wli[ivz];
-        flx[IPR] = ubar * wli[IDN] * hl;
-    } else {
-        // volume mixing ratio to mass mixing ratio
-        for n in 1..=NVAPOR {
-            rd -= wri[n];
+
+        for k in 1..nz - 1 {
+            let balance = (flx[k][IVY] - flx[k - 1][IVY]) / dz + grav * rho[k];
+            approx::assert_abs_diff_eq!(balance, 0.0, epsilon = 1e-10);
         }
 
-        flx[IDN] = ubar * wri[IDN] * rd;
-        for n in 1..=NVAPOR {
-            flx[n] = ubar * wri[IDN] * wri[n];
+        for k in 0..nz - 1 {
+            let wl = dry_state([rho[k], u0, 0.0, 0.0, pres[k]]);
+            let wr = dry_state([rho[k + 1], u0, 0.0, 0.0, pres[k + 1]]);
+            let flx = flux(&wl, &wr, X2DIR);
+
+            let rhobar = 0.5 * (rho[k] + rho[k + 1]);
+            let pbar = 0.5 * (pres[k] + pres[k + 1]);
+            let cbar = (1.4 * pbar / rhobar).sqrt();
+            let ubar = 0.5 * (pres[k] - pres[k + 1]) / (rhobar * cbar);
+            let enthalpy = 0.5 * u0 * u0 + 3.5 * pres[k] / rho[k];
+
+            assert!(ubar > 0.0);
+            for (f, expected) in [
+                (flx[IDN], ubar * rho[k]),
+                (flx[IVX], ubar * rho[k] * u0),
+                (flx[IVY], pbar),
+                (flx[IPR], ubar * rho[k] * enthalpy),
+            ] {
+                approx::assert_relative_eq!(
+                    f,
+                    expected,
+                    max_relative = 1e-12
+                );
+            }
         }
-        flx[ivx] = ubar * wri[IDN] * wri[ivx] + pbar;
-        flx[ivy] = ubar * wri[IDN] * wri[ivy];
-        flx[ivz] = ubar * wri[IDN] * wri[ivz];
-        flx[IPR] = ubar * wri[IDN] * hr;
+    }
+
+    /// The numerical dissipation of the pressure flux scales with the
+    /// velocity jump and not with the sound speed squared
+    #[test]
+    fn test_low_mach_dissipation() {
+        let pres = 1e5;
+        let cs = (1.4 * pres / 1.2 as Real).sqrt();
+
+        for mach in [1e-2, 1e-3, 1e-4] {
+            let du = mach * cs;
+            let wl = dry_state([1.2, du, 0.0, 0.0, pres]);
+            let wr = dry_state([1.2, -du, 0.0, 0.0, pres]);
+
+            // converging flow, the interface velocity vanishes
+            let flx = flux(&wl, &wr, X1DIR);
+            approx::assert_abs_diff_eq!(flx[IDN], 0.0);
+
+            let dissipation = flx[IVX] - pres;
+            approx::assert_relative_eq!(
+                dissipation,
+                1.2 * cs * du,
+                max_relative = 1e-6
+            );
+        }
+    }
+
+    /// The pressure is taken from the equation of state and not from the
+    /// pressure slot of the primitive variables, which shallow water leaves
+    /// unset
+    #[test]
+    fn test_shallow_water_pressure() {
+        use crate::eos::shallow_water::ShallowWater;
+
+        let eos = ShallowWater { grav: 9.8 };
+        let w = dry_state([2.0, 0.5, 0.0, 0.0, 0.0]);
+        let e = eos.energy(&w, &LOCAL);
+
+        let mut flx = [0.0; NHYDRO];
+        unsafe {
+            lmars(flx.as_mut_ptr(), &w, &w, X1DIR, &LOCAL, &[0], &eos);
+        }
+
+        approx::assert_relative_eq!(flx[IDN], 1.0);
+        approx::assert_relative_eq!(flx[IVX], 0.5 + 19.6);
+        approx::assert_relative_eq!(flx[IPR], 0.5 * (e + 19.6));
     }
 }
@@ -1 +1,8 @@
+pub mod exact;
+pub mod flux;
+pub mod hllc;
+pub mod hlle;
+pub mod lmars;
+pub mod riemann_solver;
 pub mod roe_shallow_water;
+pub mod rusanov;
@@ -0,0 +1,114 @@
+//! Riemann solver selection
+//!
+//! RiemannSolver dispatches to one of the interface flux functions, all of
+//! which share the calling convention of roe_shallow_water. The solver is
+//! chosen at run time, e.g. parsed from a configuration string.
+
+use std::str::FromStr;
+use crate::eos::eos_trait;
+use crate::riemann::{
+    hllc::hllc, hlle::hlle, lmars::lmars,
+    roe_shallow_water::roe_shallow_water, rusanov::rusanov,
+};
+use crate::utils::defs::{Real, NHYDRO};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum RiemannSolver {
+    #[default]
+    RoeShallowWater,
+    Lmars,
+    Hllc,
+    Hlle,
+    Rusanov,
+}
+
+impl RiemannSolver {
+    /// # Safety
+    ///
+    /// This function is unsafe because it dereferences raw pointers.
+    ///
+    /// Write the flux through the interface between the left state wl and
+    /// the right state wr in the direction dir to the raw pointer flx.
+    #[allow(clippy::too_many_arguments)]
+    pub unsafe fn solve<E: eos_trait::EquationOfState>(
+        &self,
+        flx: *mut Real,
+        wl: &[Real],
+        wr: &[Real],
+        dir: usize,
+        comps: &[usize; NHYDRO],
+        pos: &[usize],
+        eos: &E,
+    ) {
+        unsafe {
+            match self {
+                RiemannSolver::RoeShallowWater => {
+                    roe_shallow_water(flx, wl, wr, dir, comps, pos, eos)
+                },
+                RiemannSolver::Lmars => {
+                    lmars(flx, wl, wr, dir, comps, pos, eos)
+                },
+                RiemannSolver::Hllc => hllc(flx, wl, wr, dir, comps, pos, eos),
+                RiemannSolver::Hlle => hlle(flx, wl, wr, dir, comps, pos, eos),
+                RiemannSolver::Rusanov => {
+                    rusanov(flx, wl, wr, dir, comps, pos, eos)
+                },
+            }
+        }
+    }
+}
+
+impl FromStr for RiemannSolver {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "roe" => Ok(RiemannSolver::RoeShallowWater),
+            "lmars" => Ok(RiemannSolver::Lmars),
+            "hllc" => Ok(RiemannSolver::Hllc),
+            "hlle" => Ok(RiemannSolver::Hlle),
+            "rusanov" | "llf" => Ok(RiemannSolver::Rusanov),
+            _ => Err(format!("Unknown Riemann solver: {}", name)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eos::ideal_gas::IdealGas;
+    use crate::riemann::flux::LOCAL;
+    use crate::utils::{common::dry_state, defs::X2DIR};
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("hllc".parse::<RiemannSolver>(), Ok(RiemannSolver::Hllc));
+        assert_eq!("llf".parse::<RiemannSolver>(), Ok(RiemannSolver::Rusanov));
+        assert!("exact".parse::<RiemannSolver>().is_err());
+    }
+
+    /// The dispatch gives the flux of the selected solver
+    #[test]
+    fn test_solve() {
+        let eos = IdealGas { gamma: 1.4 };
+        let wl = dry_state([1.0, 0.1, 0.2, 0.0, 1.0]);
+        let wr = dry_state([0.5, -0.1, 0.3, 0.0, 0.4]);
+
+        let mut expected = [0.0; NHYDRO];
+        let mut flx = [0.0; NHYDRO];
+        unsafe {
+            hlle(expected.as_mut_ptr(), &wl, &wr, X2DIR, &LOCAL, &[0], &eos);
+            RiemannSolver::Hlle.solve(
+                flx.as_mut_ptr(),
+                &wl,
+                &wr,
+                X2DIR,
+                &LOCAL,
+                &[0],
+                &eos,
+            );
+        }
+
+        assert_eq!(flx, expected);
+    }
+}
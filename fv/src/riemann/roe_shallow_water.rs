@@ -1,23 +1,33 @@
 /// ! Roe shallow water Riemann Solver
+///
+/// The wave speeds carry the Harten-Hyman entropy fix, which spreads the
+/// acoustic waves across transonic rarefactions. Cells shallower than
+/// DRY_DEPTH are dry: their velocity and pressure are cut off and the
+/// solver never takes the square root of a negative height.
+use crate::eos::{eos_trait, shallow_water::DRY_DEPTH};
 use crate::utils::{
     common::{add_comp, set_comp},
-    defs::{Real, NHYDRO, X1DIR, X2DIR},
+    defs::{Real, IDN, IVX, IVY, NHYDRO, X1DIR, X2DIR},
 };
 
 /// # Safety
 ///
 /// This function is unsafe because it dereferences raw pointers.
-pub unsafe fn roe_shallow_water(
+///
+/// The equation of state must be shallow water, where the pressure is
+/// g h^2 / 2 and the gravity wave speed is sqrt(g h).
+pub unsafe fn roe_shallow_water<E: eos_trait::EquationOfState>(
     flx: *mut Real,
     wli: &[Real],
     wri: &[Real],
     dir: usize,
     comps: &[usize; NHYDRO],
     _pos: &[usize],
+    eos: &E,
 ) {
-    let idn = comps[0];
-    let iv1 = comps[1];
-    let iv2 = comps[2];
+    let idn = comps[IDN];
+    let iv1 = comps[IVX];
+    let iv2 = comps[IVY];
 
     let (ivx, ivy) = match dir {
         X1DIR => (iv1, iv2),
@@ -32,16 +42,38 @@ pub unsafe fn roe_shallow_water(
     // let idx = thread::index_1d();
     // flx, wli, wri = shift_position!(pos, i, flx, wli, wri)
 
-    let ubar = (wli[ivx] * wli[idn].sqrt() + wri[ivx] * wri[idn].sqrt())
-        / (wli[idn].sqrt() + wri[idn].sqrt());
-    let vbar = (wli[ivy] * wli[idn].sqrt() + wri[ivy] * wri[idn].sqrt())
-        / (wli[idn].sqrt() + wri[idn].sqrt());
-    let cbar = (0.5 * (wli[idn] + wri[idn])).sqrt();
+    // height, velocities, pressure and wave speed with the dry cut-off
+    let state = |w: &[Real]| {
+        if w[idn] > DRY_DEPTH {
+            (
+                w[idn],
+                w[ivx],
+                w[ivy],
+                eos.pressure(w, comps),
+                eos.sound_speed(w, comps),
+            )
+        } else {
+            (w[idn].max(0.0), 0.0, 0.0, 0.0, 0.0)
+        }
+    };
+    let (hl, ul, vl, pl, cl) = state(wli);
+    let (hr, ur, vr, pr, cr) = state(wri);
+
+    if cl == 0.0 && cr == 0.0 {
+        set_comp(flx, idn, 0.0);
+        set_comp(flx, ivx, 0.0);
+        set_comp(flx, ivy, 0.0);
+        return;
+    }
+
+    let ubar = (ul * hl.sqrt() + ur * hr.sqrt()) / (hl.sqrt() + hr.sqrt());
+    let vbar = (vl * hl.sqrt() + vr * hr.sqrt()) / (hl.sqrt() + hr.sqrt());
+    let cbar = (0.5 * (cl * cl + cr * cr)).sqrt();
 
-    let delh = wri[idn] - wli[idn];
-    let delu = wri[ivx] - wli[ivx];
-    let delv = wri[ivy] - wli[ivy];
-    let hbar = (wli[idn] * wri[idn]).sqrt();
+    let delh = hr - hl;
+    let delu = ur - ul;
+    let delv = vr - vl;
+    let hbar = (hl * hr).sqrt();
 
     let a1 = 0.5 * (cbar * delh - hbar * delu) / cbar;
     let a2 = hbar * delv;
@@ -57,25 +89,13 @@ pub unsafe fn roe_shallow_water(
     wave[2][1] = a3 * (ubar + cbar);
     wave[2][2] = a3 * vbar;
 
-    speed[0] = (ubar - cbar).abs();
+    speed[0] = entropy_fix(ubar - cbar, ul - cl, ur - cr);
     speed[1] = ubar.abs();
-    speed[2] = (ubar + cbar).abs();
-
-    set_comp(flx, idn, 0.5 * (wli[idn] * wli[ivx] + wri[idn] * wri[ivx]));
-    set_comp(
-        flx,
-        ivx,
-        0.5 * (wli[idn] * wli[ivx].powi(2)
-            + 0.5 * wli[idn].powi(2)
-            + wri[idn] * wri[ivx].powi(2)
-            + 0.5 * wri[idn].powi(2)),
-    );
-    set_comp(
-        flx,
-        ivy,
-        0.5 * (wli[idn] * wli[ivx] * wli[ivy]
-            + wri[idn] * wri[ivx] * wri[ivy]),
-    );
+    speed[2] = entropy_fix(ubar + cbar, ul + cl, ur + cr);
+
+    set_comp(flx, idn, 0.5 * (hl * ul + hr * ur));
+    set_comp(flx, ivx, 0.5 * (hl * ul * ul + pl + hr * ur * ur + pr));
+    set_comp(flx, ivy, 0.5 * (hl * ul * vl + hr * ur * vr));
 
     for r in 0..3 {
         add_comp(flx, idn, -0.5 * speed[r] * wave[r][0]);
@@ -83,3 +103,149 @@ pub unsafe fn roe_shallow_water(
         add_comp(flx, ivy, -0.5 * speed[r] * wave[r][2]);
     }
 }
+
+/// Harten-Hyman entropy fix of the Roe wave speed lambda, given the
+/// characteristic speeds of the left and right states
+fn entropy_fix(lambda: Real, left: Real, right: Real) -> Real {
+    let delta = (lambda - left).max(right - lambda).max(0.0);
+    if lambda.abs() < delta {
+        0.5 * (lambda * lambda + delta * delta) / delta
+    } else {
+        lambda.abs()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eos::shallow_water::ShallowWater;
+    use crate::riemann::{
+        exact::ExactRiemann,
+        flux::{self, LOCAL},
+    };
+    use crate::utils::common::dry_state;
+
+    /// The flux of identical left and right states is the physical flux
+    #[test]
+    fn test_consistency() {
+        let eos = ShallowWater { grav: 9.8 };
+        let comps = LOCAL;
+        let w = dry_state([2.0, 0.5, -1.0, 0.0, 0.0]);
+
+        let flux = |dir| {
+            let mut flx = [0.0; NHYDRO];
+            unsafe {
+                roe_shallow_water(
+                    flx.as_mut_ptr(),
+                    &w,
+                    &w,
+                    dir,
+                    &comps,
+                    &[0],
+                    &eos,
+                );
+            }
+            flx
+        };
+
+        let flx = flux(X1DIR);
+        approx::assert_abs_diff_eq!(flx[IDN], 1.0);
+        approx::assert_abs_diff_eq!(flx[IVX], 0.5 + 19.6, epsilon = 1e-12);
+        approx::assert_abs_diff_eq!(flx[IVY], -1.0);
+
+        let flx = flux(X2DIR);
+        approx::assert_abs_diff_eq!(flx[IDN], -2.0);
+        approx::assert_abs_diff_eq!(flx[IVX], -1.0);
+        approx::assert_abs_diff_eq!(flx[IVY], 2.0 + 19.6, epsilon = 1e-12);
+    }
+
+    /// A dam break releases a positive mass flux toward the shallow side
+    /// that grows with gravity
+    #[test]
+    fn test_dam_break() {
+        let comps = LOCAL;
+        let wl = dry_state([2.0, 0.0, 0.0, 0.0, 0.0]);
+        let wr = dry_state([1.0, 0.0, 0.0, 0.0, 0.0]);
+
+        let mass_flux = |grav| {
+            let mut flx = [0.0; NHYDRO];
+            let eos = ShallowWater { grav };
+            unsafe {
+                roe_shallow_water(
+                    flx.as_mut_ptr(),
+                    &wl,
+                    &wr,
+                    X1DIR,
+                    &comps,
+                    &[0],
+                    &eos,
+                );
+            }
+            flx[IDN]
+        };
+
+        assert!(mass_flux(1.0) > 0.0);
+        approx::assert_abs_diff_eq!(
+            mass_flux(4.0),
+            2.0 * mass_flux(1.0),
+            epsilon = 1e-12
+        );
+    }
+
+    /// A transonic rarefaction spreads through the interface instead of
+    /// standing as an expansion shock
+    #[test]
+    fn test_transonic_rarefaction() {
+        let eos = ShallowWater { grav: 1.0 };
+        let comps = LOCAL;
+        let wl = dry_state([1.0, 0.5, 0.0, 0.0, 0.0]);
+        let wr = dry_state([0.5, 1.5, 0.0, 0.0, 0.0]);
+
+        let mut flx = [0.0; NHYDRO];
+        unsafe {
+            roe_shallow_water(
+                flx.as_mut_ptr(),
+                &wl,
+                &wr,
+                X1DIR,
+                &comps,
+                &[0],
+                &eos,
+            );
+        }
+
+        // the exact interface state lies inside the rarefaction fan
+        let w = eos.sample(&wl, &wr, IVX, 0.0);
+        approx::assert_relative_eq!(
+            flx[IDN],
+            w[IDN] * w[IVX],
+            max_relative = 0.1
+        );
+    }
+
+    /// Ritter dam break onto a dry bed stays finite and non-negative and
+    /// tracks the exact solution
+    #[test]
+    fn test_dry_dam_break() {
+        let eos = ShallowWater { grav: 1.0 };
+        let wl = dry_state([1.0, 0.0, 0.0, 0.0, 0.0]);
+        let wr = dry_state([0.0, 0.0, 0.0, 0.0, 0.0]);
+        let solver = |flx, l: &[Real], r: &[Real]| unsafe {
+            roe_shallow_water(flx, l, r, X1DIR, &LOCAL, &[0], &eos)
+        };
+
+        let tmax = 0.1;
+        let w = flux::shock_tube(&eos, solver, wl, wr, tmax);
+
+        let mut error = 0.0;
+        for (i, w) in w.iter().enumerate() {
+            assert!(w[IDN].is_finite() && w[IVX].is_finite());
+            assert!(w[IDN] >= 0.0);
+
+            let xi = ((i as Real + 0.5) / 400.0 - 0.5) / tmax;
+            let exact = eos.sample(&wl, &wr, IVX, xi);
+            error += (w[IDN] - exact[IDN]).abs() / 400.0;
+        }
+        assert!(error < 5e-3, "L1 height error = {}", error);
+    }
+}
@@ -0,0 +1,117 @@
+//! Rusanov (local Lax-Friedrichs) Riemann solver
+//! Reference: Rusanov (1961)
+//!
+//! Central flux with the dissipation of the fastest local signal speed.
+//! It is the most dissipative and most robust of the solvers. The signal
+//! speed comes from the sound speed of the equation of state, so the solver
+//! works for both the Euler and the shallow-water systems.
+
+use crate::eos::eos_trait;
+use crate::riemann::flux::{conserved, gather, physical_flux, LOCAL};
+use crate::utils::{
+    common::set_comp,
+    defs::{Real, IVX, IVY, NHYDRO, X1DIR, X2DIR},
+};
+
+/// # Safety
+///
+/// This function is unsafe because it dereferences raw pointers.
+pub unsafe fn rusanov<E: eos_trait::EquationOfState>(
+    flx: *mut Real,
+    wli: &[Real],
+    wri: &[Real],
+    dir: usize,
+    comps: &[usize; NHYDRO],
+    _pos: &[usize],
+    eos: &E,
+) {
+    let ivx = match dir {
+        X1DIR => IVX,
+        X2DIR => IVY,
+        _ => panic!("Invalid direction"),
+    };
+
+    let wl = gather(wli, comps);
+    let wr = gather(wri, comps);
+    let ul = conserved(&wl, eos);
+    let ur = conserved(&wr, eos);
+
+    let smax = (wl[ivx].abs() + eos.sound_speed(&wl, &LOCAL))
+        .max(wr[ivx].abs() + eos.sound_speed(&wr, &LOCAL));
+
+    let fl = physical_flux(&ul, &wl, eos.pressure(&wl, &LOCAL), ivx);
+    let fr = physical_flux(&ur, &wr, eos.pressure(&wr, &LOCAL), ivx);
+
+    for n in 0..NHYDRO {
+        let f = 0.5 * (fl[n] + fr[n]) - 0.5 * smax * (ur[n] - ul[n]);
+        set_comp(flx, comps[n], f);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eos::{ideal_gas::IdealGas, shallow_water::ShallowWater};
+    use crate::riemann::flux;
+    use crate::utils::{
+        common::dry_state,
+        defs::{IDN, IPR},
+    };
+
+    /// The flux of identical left and right states is the physical flux
+    #[test]
+    fn test_consistency() {
+        let eos = ShallowWater { grav: 9.8 };
+        let w = dry_state([2.0, 0.5, -1.0, 0.0, 0.0]);
+
+        let mut flx = [0.0; NHYDRO];
+        unsafe {
+            rusanov(flx.as_mut_ptr(), &w, &w, X1DIR, &LOCAL, &[0], &eos)
+        };
+
+        approx::assert_abs_diff_eq!(flx[IDN], 1.0);
+        approx::assert_abs_diff_eq!(flx[IVX], 0.5 + 19.6, epsilon = 1e-12);
+        approx::assert_abs_diff_eq!(flx[IVY], -1.0);
+    }
+
+    /// Sod shock tube, p* = 0.30313, u* = 0.92745
+    #[test]
+    fn test_sod() {
+        let eos = IdealGas { gamma: 1.4 };
+        let solver = |flx, l: &[Real], r: &[Real]| unsafe {
+            rusanov(flx, l, r, X1DIR, &LOCAL, &[0], &eos)
+        };
+        let w = flux::shock_tube(
+            &eos,
+            solver,
+            dry_state([1.0, 0.0, 0.0, 0.0, 1.0]),
+            dry_state([0.125, 0.0, 0.0, 0.0, 0.1]),
+            0.2,
+        );
+
+        let star = w[(0.70 * 400.0) as usize];
+        approx::assert_relative_eq!(star[IVX], 0.92745, max_relative = 0.02);
+        approx::assert_relative_eq!(star[IPR], 0.30313, max_relative = 0.02);
+    }
+
+    /// Shallow-water dam break into a nearly dry bed keeps the height
+    /// positive
+    #[test]
+    fn test_dam_break_dry() {
+        let eos = ShallowWater { grav: 1.0 };
+        let solver = |flx, l: &[Real], r: &[Real]| unsafe {
+            rusanov(flx, l, r, X1DIR, &LOCAL, &[0], &eos)
+        };
+        let w = flux::shock_tube(
+            &eos,
+            solver,
+            dry_state([1.0, 0.0, 0.0, 0.0, 0.0]),
+            dry_state([1e-6, 0.0, 0.0, 0.0, 0.0]),
+            0.2,
+        );
+
+        for w in &w {
+            assert!(w[IDN] > 0.0);
+        }
+    }
+}
@@ -0,0 +1,2 @@
+pub mod saturation;
+pub mod thermodynamics;
@@ -0,0 +1,77 @@
+//! Saturation vapor pressure
+//!
+//! Ideal Clausius-Clapeyron saturation curves with a constant latent heat,
+//! p_sat(T) = p3 exp(beta (1 - t3 / T)), where (t3, p3) is the triple point
+//! and beta = L / (R_v t3) is the latent heat L per unit mass in units of
+//! the thermal energy of the vapor at the triple point.
+
+use crate::utils::defs::Real;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SaturationCurve {
+    // triple point temperature
+    pub t3: Real,
+
+    // triple point pressure
+    pub p3: Real,
+
+    // dimensionless latent heat
+    pub beta: Real,
+}
+
+impl SaturationCurve {
+    /// Water, L = 2.5e6 J/kg and R_v = 461.5 J/kg/K
+    pub fn water() -> Self {
+        Self {
+            t3: 273.16,
+            p3: 611.7,
+            beta: 19.83,
+        }
+    }
+
+    /// Ammonia, L = 1.4e6 J/kg and R_v = 488.2 J/kg/K
+    pub fn ammonia() -> Self {
+        Self {
+            t3: 195.4,
+            p3: 6060.0,
+            beta: 14.68,
+        }
+    }
+
+    /// Saturation vapor pressure at temperature temp
+    pub fn pressure(&self, temp: Real) -> Real {
+        self.p3 * (self.beta * (1.0 - self.t3 / temp)).exp()
+    }
+
+    /// Logarithmic derivative of the saturation vapor pressure,
+    /// d ln(p_sat) / dT
+    pub fn dlnp_dt(&self, temp: Real) -> Real {
+        self.beta * self.t3 / (temp * temp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_water() {
+        let water = SaturationCurve::water();
+
+        approx::assert_abs_diff_eq!(water.pressure(273.16), 611.7);
+
+        // about 3.5 kPa at 300 K
+        let psat = water.pressure(300.0);
+        assert!((3.0e3..4.0e3).contains(&psat), "p_sat = {}", psat);
+
+        let dt = 1e-3;
+        let dlnp = (water.pressure(300.0 + dt).ln()
+            - water.pressure(300.0 - dt).ln())
+            / (2.0 * dt);
+        approx::assert_abs_diff_eq!(
+            dlnp,
+            water.dlnp_dt(300.0),
+            epsilon = 1e-8
+        );
+    }
+}
@@ -0,0 +1,375 @@
+//! Thermodynamics module
+//!
+//! Thermodynamic properties of a mixture of dry air and vapor species. The
+//! properties of each vapor are given relative to dry air:
+//! 1. cv_ratio, the ratio of the specific heats at constant volume
+//! 2. mass_ratio, the ratio of the molecular weights
+//!
+//! Species 0 is dry air and species 1..=nvapor are the vapors, matching the
+//! layout of the vapor mass mixing ratios in the primitive variables.
+//!
+//! A vapor with a saturation curve condenses into a cloud species,
+//! releasing the latent heat of its saturation curve. Clouds are species
+//! of their own, carried with the flow like the vapors, with the heat
+//! capacity of their vapor and an infinite mass ratio so that they add no
+//! moles to the gas.
+
+use crate::thermo::saturation::SaturationCurve;
+use crate::utils::defs::Real;
+
+// relative tolerance and iteration limits of the saturation adjustment
+const TOL: Real = 1e-12;
+const MAX_ITERS: usize = 20;
+const MAX_SWEEPS: usize = 10;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Thermodynamics {
+    // specific heat at constant volume relative to dry air
+    pub cv_ratio: Vec<Real>,
+
+    // molecular weight relative to dry air
+    pub mass_ratio: Vec<Real>,
+
+    // gas constant of dry air
+    pub rd: Real,
+
+    // saturation curve of each condensable vapor
+    pub saturation: Vec<Option<SaturationCurve>>,
+
+    // cloud species of each condensable vapor
+    pub cloud: Vec<Option<usize>>,
+}
+
+impl Thermodynamics {
+    /// Mixture of dry air and vapors with the given property ratios
+    pub fn new(cv_ratio: &[Real], mass_ratio: &[Real]) -> Self {
+        assert_eq!(cv_ratio.len(), mass_ratio.len());
+
+        Self {
+            cv_ratio: [&[1.0], cv_ratio].concat(),
+            mass_ratio: [&[1.0], mass_ratio].concat(),
+            rd: 287.0,
+            saturation: vec![None; cv_ratio.len() + 1],
+            cloud: vec![None; cv_ratio.len() + 1],
+        }
+    }
+
+    pub fn rd(&mut self, rd: Real) -> &mut Self {
+        self.rd = rd;
+        self
+    }
+
+    /// Make vapor n condensable into species cloud with the given
+    /// saturation curve
+    pub fn saturation(
+        &mut self,
+        n: usize,
+        cloud: usize,
+        curve: SaturationCurve,
+    ) -> &mut Self {
+        let species = 1..=self.nvapor();
+        assert!(species.contains(&n), "Invalid vapor {}", n);
+        assert!(
+            species.contains(&cloud) && cloud != n,
+            "Invalid cloud {}",
+            cloud
+        );
+        assert!(
+            self.mass_ratio[cloud].is_infinite()
+                && self.cv_ratio[cloud] == self.cv_ratio[n],
+            "Cloud {} is not a condensate of vapor {}",
+            cloud,
+            n
+        );
+        self.saturation[n] = Some(curve);
+        self.cloud[n] = Some(cloud);
+        self
+    }
+
+    /// Number of vapor species
+    pub fn nvapor(&self) -> usize {
+        self.cv_ratio.len() - 1
+    }
+
+    /// Heat capacity of the mixture relative to dry air,
+    /// 1 + sum_n q_n (cv_ratio_n - 1)
+    ///
+    /// The mass mixing ratio q_n of vapor n is w[comps[n]].
+    pub fn fsig(&self, w: &[Real], comps: &[usize]) -> Real {
+        (1..=self.nvapor()).fold(1.0, |fsig, n| {
+            fsig + w[comps[n]] * (self.cv_ratio[n] - 1.0)
+        })
+    }
+
+    /// Moles of the mixture relative to dry air of the same mass,
+    /// 1 + sum_n q_n (1 / mass_ratio_n - 1)
+    ///
+    /// The mass mixing ratio q_n of vapor n is w[comps[n]].
+    pub fn feps(&self, w: &[Real], comps: &[usize]) -> Real {
+        (1..=self.nvapor()).fold(1.0, |feps, n| {
+            feps + w[comps[n]] * (1.0 / self.mass_ratio[n] - 1.0)
+        })
+    }
+
+    /// Gas constant of species n
+    pub fn gas_constant(&self, n: usize) -> Real {
+        self.rd / self.mass_ratio[n]
+    }
+
+    /// Latent heat per unit mass of condensable vapor n
+    pub fn latent_heat(&self, n: usize) -> Real {
+        self.saturation[n]
+            .map_or(0.0, |sat| sat.beta * self.gas_constant(n) * sat.t3)
+    }
+
+    /// Latent energy released by condensing the clouds,
+    /// sum_n L_n q_c(n) over the condensable vapors n
+    ///
+    /// The mass mixing ratio of cloud c(n) is w[comps[c(n)]].
+    pub fn latent(&self, w: &[Real], comps: &[usize]) -> Real {
+        (1..=self.nvapor()).fold(0.0, |latent, n| match self.cloud[n] {
+            Some(c) => latent + self.latent_heat(n) * w[comps[c]],
+            None => latent,
+        })
+    }
+
+    /// Heat capacity per unit volume of a gas of dry air density rho_d and
+    /// species densities rho_v, where cv_dry is the specific heat of dry air
+    /// at constant volume
+    pub fn heat_capacity(
+        &self,
+        cv_dry: Real,
+        rho_d: Real,
+        rho_v: &[Real],
+    ) -> Real {
+        rho_v.iter().enumerate().fold(cv_dry * rho_d, |cv, (i, rho)| {
+            cv + cv_dry * self.cv_ratio[i + 1] * rho
+        })
+    }
+
+    /// Temperature of a gas of dry air density rho_d and vapor densities
+    /// rho_v at pressure pres
+    pub fn temperature(&self, pres: Real, rho_d: Real, rho_v: &[Real]) -> Real {
+        pres / self.density_gas_constant(rho_d, rho_v)
+    }
+
+    /// Pressure of a gas of dry air density rho_d and vapor densities rho_v
+    /// at temperature temp
+    pub fn pressure(&self, temp: Real, rho_d: Real, rho_v: &[Real]) -> Real {
+        temp * self.density_gas_constant(rho_d, rho_v)
+    }
+
+    /// sum_n rho_n R_n over dry air and the vapors, the clouds add nothing
+    fn density_gas_constant(&self, rho_d: Real, rho_v: &[Real]) -> Real {
+        rho_v
+            .iter()
+            .enumerate()
+            .fold(rho_d * self.rd, |rho_r, (i, rho)| {
+                rho_r + rho * self.gas_constant(i + 1)
+            })
+    }
+
+    /// Condense supersaturated vapor into clouds and evaporate clouds into
+    /// subsaturated vapor at constant volume and internal energy
+    ///
+    /// rho_v are the densities of the species 1..=nvapor, vapors and
+    /// clouds, cv_dry is the specific heat of dry air at constant volume.
+    /// Returns the adjusted temperature.
+    pub fn saturation_adjustment(
+        &self,
+        cv_dry: Real,
+        temp: Real,
+        rho_d: Real,
+        rho_v: &mut [Real],
+    ) -> Real {
+        // unchanged by phase changes, clouds share the heat capacity of
+        // their vapor
+        let heat_capacity = self.heat_capacity(cv_dry, rho_d, rho_v);
+
+        let mut temp = temp;
+        for _ in 0..MAX_SWEEPS {
+            let mut converged = true;
+
+            for i in 0..rho_v.len() {
+                let (sat, c) = match (self.saturation[i + 1], self.cloud[i + 1])
+                {
+                    (Some(sat), Some(c)) => (sat, c - 1),
+                    _ => continue,
+                };
+                let rv = self.gas_constant(i + 1);
+                let heating = self.latent_heat(i + 1) / heat_capacity;
+
+                // saturation density after condensing the density dq
+                let rho_sat = |dq: Real| {
+                    let t = temp + heating * dq;
+                    (sat.pressure(t) / (rv * t), sat.dlnp_dt(t) - 1.0 / t)
+                };
+
+                // Newton iteration for rho_v - dq = rho_sat(dq)
+                let mut dq = 0.0;
+                for _ in 0..MAX_ITERS {
+                    let (rho_s, dln) = rho_sat(dq);
+                    let f = rho_v[i] - dq - rho_s;
+                    let df = -1.0 - rho_s * dln * heating;
+                    let dq_new = (dq - f / df).clamp(-rho_v[c], rho_v[i]);
+                    let done = (dq_new - dq).abs() <= TOL * rho_v[i].max(rho_s);
+                    dq = dq_new;
+                    if done {
+                        break;
+                    }
+                }
+
+                if dq.abs() > TOL * rho_v[i] {
+                    converged = false;
+                }
+
+                rho_v[i] -= dq;
+                rho_v[c] += dq;
+                temp += heating * dq;
+            }
+
+            if converged {
+                break;
+            }
+        }
+
+        temp
+    }
+
+    /// Total mass mixing ratio of the vapors and clouds
+    pub fn vapor_fraction(&self, w: &[Real], comps: &[usize]) -> Real {
+        (1..=self.nvapor()).map(|n| w[comps[n]]).sum()
+    }
+}
+
+impl Default for Thermodynamics {
+    /// Dry air without vapors
+    fn default() -> Self {
+        Self::new(&[], &[])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dry() {
+        let thermo = Thermodynamics::default();
+        let w = [1.2, 0.0, 0.0, 0.0, 1.0];
+
+        assert_eq!(thermo.nvapor(), 0);
+        assert_eq!(thermo.fsig(&w, &[0, 1, 2, 3, 4]), 1.0);
+        assert_eq!(thermo.feps(&w, &[0, 1, 2, 3, 4]), 1.0);
+    }
+
+    #[test]
+    fn test_moist() {
+        // water and ammonia in a hydrogen atmosphere
+        let thermo = Thermodynamics::new(&[1.5, 1.3], &[9.0, 8.5]);
+        let w = [1.0, 0.02, 0.01];
+        let comps = [0, 1, 2];
+
+        assert_eq!(thermo.nvapor(), 2);
+        approx::assert_abs_diff_eq!(
+            thermo.fsig(&w, &comps),
+            1.0 + 0.02 * 0.5 + 0.01 * 0.3,
+            epsilon = 1e-15
+        );
+        approx::assert_abs_diff_eq!(
+            thermo.feps(&w, &comps),
+            1.0 + 0.02 * (1.0 / 9.0 - 1.0) + 0.01 * (1.0 / 8.5 - 1.0),
+            epsilon = 1e-15
+        );
+        approx::assert_abs_diff_eq!(thermo.vapor_fraction(&w, &comps), 0.03);
+    }
+
+    /// Moist air at 300 K with water vapor 1 above saturation, condensing
+    /// into cloud 2
+    fn moist_air() -> (Thermodynamics, Real, Real) {
+        let mut thermo =
+            Thermodynamics::new(&[1.46, 1.46], &[0.622, Real::INFINITY]);
+        thermo.saturation(1, 2, SaturationCurve::water());
+        let cv_dry = thermo.rd / 0.4;
+        (thermo, cv_dry, 1.1)
+    }
+
+    #[test]
+    fn test_clouds() {
+        let (thermo, _, _) = moist_air();
+        let w = [1.0, 0.02, 0.01];
+        let comps = [0, 1, 2];
+
+        assert_eq!(thermo.gas_constant(2), 0.0);
+        approx::assert_abs_diff_eq!(
+            thermo.feps(&w, &comps),
+            1.0 + 0.02 * (1.0 / 0.622 - 1.0) - 0.01,
+            epsilon = 1e-15
+        );
+        approx::assert_relative_eq!(
+            thermo.latent(&w, &comps),
+            0.01 * thermo.latent_heat(1),
+            max_relative = 1e-15
+        );
+        approx::assert_relative_eq!(
+            thermo.pressure(300.0, 1.1, &[0.02, 0.01]),
+            300.0 * (1.1 * thermo.rd + 0.02 * thermo.gas_constant(1)),
+            max_relative = 1e-15
+        );
+    }
+
+    #[test]
+    fn test_condensation() {
+        let (thermo, cv_dry, rho_d) = moist_air();
+        let mut rho_v = [0.04, 0.0];
+        let temp0 = 300.0;
+
+        let internal_energy = |temp: Real, rho_v: &[Real]| {
+            let cv = cv_dry * (rho_d + 1.46 * (rho_v[0] + rho_v[1]));
+            cv * temp - thermo.latent_heat(1) * rho_v[1]
+        };
+        let energy0 = internal_energy(temp0, &rho_v);
+
+        let temp =
+            thermo.saturation_adjustment(cv_dry, temp0, rho_d, &mut rho_v);
+
+        let water = SaturationCurve::water();
+        let rho_sat = water.pressure(temp) / (thermo.gas_constant(1) * temp);
+
+        assert!(rho_v[1] > 0.0 && temp > temp0);
+        approx::assert_relative_eq!(rho_v[0], rho_sat, max_relative = 1e-10);
+        approx::assert_abs_diff_eq!(rho_v[0] + rho_v[1], 0.04, epsilon = 1e-15);
+        approx::assert_relative_eq!(
+            internal_energy(temp, &rho_v),
+            energy0,
+            max_relative = 1e-14
+        );
+    }
+
+    #[test]
+    fn test_evaporation() {
+        let (thermo, cv_dry, rho_d) = moist_air();
+
+        // subsaturated vapor evaporates all of a thin cloud
+        let mut rho_v = [0.001, 1e-4];
+        let temp =
+            thermo.saturation_adjustment(cv_dry, 300.0, rho_d, &mut rho_v);
+
+        assert!(temp < 300.0);
+        assert_eq!(rho_v[1], 0.0);
+        approx::assert_abs_diff_eq!(rho_v[0], 0.0011, epsilon = 1e-15);
+
+        // vapor without a saturation curve never condenses
+        let dry = Thermodynamics::new(&[1.46, 1.46], &[0.622, Real::INFINITY]);
+        let mut rho_v = [0.04, 0.0];
+        let temp = dry.saturation_adjustment(cv_dry, 300.0, rho_d, &mut rho_v);
+        assert_eq!((temp, rho_v[0], rho_v[1]), (300.0, 0.04, 0.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "Cloud 2 is not a condensate of vapor 1")]
+    fn test_cloud_with_moles() {
+        let mut thermo = Thermodynamics::new(&[1.46, 1.46], &[0.622, 0.622]);
+        thermo.saturation(1, 2, SaturationCurve::water());
+    }
+}
@@ -1,5 +1,8 @@
 //! Common functions for the hydrodynamics module
 
+#[cfg(test)]
+use crate::utils::defs::{Real, IDN, IPR, IVX, IVY, IVZ, NHYDRO};
+
 /// # Safety
 ///
 /// Not recommended for use. Used in test environment only.
@@ -21,3 +24,15 @@ pub unsafe fn add_comp<T: Copy + std::ops::AddAssign>(
         *var.add(n) += val;
     }
 }
+
+/// State of dry air from its five hydrodynamic variables, the density, the
+/// three velocities (momenta) and the pressure (energy). The vapors are
+/// zero. Used in test environment only.
+#[cfg(test)]
+pub fn dry_state(w: [Real; 5]) -> [Real; NHYDRO] {
+    let mut state = [0.0; NHYDRO];
+    for (n, w) in [IDN, IVX, IVY, IVZ, IPR].into_iter().zip(w) {
+        state[n] = w;
+    }
+    state
+}
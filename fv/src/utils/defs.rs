@@ -7,9 +7,20 @@ pub const DIMENSION: usize = 2;
 // Number of ghost zones surrounding the active domain
 pub const NGHOST: usize = 3;
 
-// Number of hydrodynamic variables
-pub const NHYDRO: usize = 5;
+// Number of vapor species and hydrodynamic variables, the moist feature
+// carries water vapor and its cloud
+#[cfg(not(feature = "moist"))]
 pub const NVAPOR: usize = 0;
+#[cfg(feature = "moist")]
+pub const NVAPOR: usize = 2;
+pub const NHYDRO: usize = 5 + NVAPOR;
+
+// Variable indices, the vapor mixing ratios are stored at 1..=NVAPOR
+pub const IDN: usize = 0;
+pub const IVX: usize = 1 + NVAPOR;
+pub const IVY: usize = 2 + NVAPOR;
+pub const IVZ: usize = 3 + NVAPOR;
+pub const IPR: usize = 4 + NVAPOR;
 
 // Direction constants
 pub const X1DIR: usize = 0;